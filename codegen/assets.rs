@@ -0,0 +1,59 @@
+use std::path::Path;
+use crate::{ByteString, Map, list_dir, error::{Error, Errors}};
+
+const PREFIX: &str = "{{ASSERT}}/";
+
+/// asset paths (relative to the asset root) referenced by the generated files,
+/// with the names of the pages or code files referencing them
+#[derive(Debug, Default)]
+pub struct AssetRefs(Map<Vec<ByteString>>);
+
+impl AssetRefs {
+    pub fn new() -> AssetRefs {
+        AssetRefs(Map::new())
+    }
+
+    pub fn scan(&mut self, content: &str, source: &str) {
+        for (start, _) in content.match_indices(PREFIX) {
+            let rest = &content[(start + PREFIX.len())..];
+            let end = rest.find(|c: char| matches!(c, '"' | '\'' | '`' | '(' | ')' | '<' | '>') || c.is_whitespace()).unwrap_or(rest.len());
+            let path = &rest[..end];
+            // paths built at runtime (e.g. the backgrounds in main.ts) can't be checked
+            if path.is_empty() || path.contains("${") {
+                continue;
+            }
+            let sources = self.0.entry(s!(path)).or_default();
+            if !sources.iter().any(|s| s == source) {
+                sources.push(s!(source));
+            }
+        }
+    }
+
+    /// returns the missing assets as errors, and the icon files (`image/icon*/`) that are never referenced
+    pub fn check(&self, assets_path: &Path) -> (Errors, Vec<ByteString>) {
+        let mut errors = Errors::new();
+        for (path, sources) in &self.0 {
+            let full_path = assets_path.join(path);
+            if !full_path.is_file() {
+                errors.push(Error::invalid(s!("missing asset `", path, "` referenced by ", sources.join(", "))).at(full_path));
+            }
+        }
+
+        let mut unused = Vec::new();
+        let image_path = assets_path.join("image");
+        for (dir_name, dir_path) in errors.collect(list_dir(&image_path, true)).unwrap_or_default() {
+            if !dir_name.starts_with("icon") {
+                continue;
+            }
+            for (file_name, _) in errors.collect(list_dir(&dir_path, false)).unwrap_or_default() {
+                let path = s!("image/", dir_name, "/", file_name);
+                if !self.0.contains_key(&path) {
+                    unused.push(path);
+                }
+            }
+        }
+        unused.sort();
+
+        (errors, unused)
+    }
+}
@@ -1,16 +1,18 @@
-use std::{fs, path::Path};
+#[allow(dead_code)]
+#[path = "commit.rs"]
+mod commit;
 
-fn read_commit<P: AsRef<Path>>(base_path: P) -> String {
-    let base_path = base_path.as_ref();
-    let head = fs::read_to_string(base_path.join(".git/HEAD")).unwrap();
-    let head = head.split('\n').next().unwrap();
-    let head = head.split("ref: ").nth(1).unwrap();
-    let commit = fs::read(base_path.join(".git").join(head)).unwrap();
-    String::from_utf8(commit[0..7].to_vec()).unwrap()
-}
+use std::path::Path;
 
 fn main() {
-    println!("cargo:rerun-if-changed=../.git/HEAD");
-    println!("cargo:rerun-if-changed=../.git/refs");
-    println!("cargo:rustc-env=GIT_HASH={}", read_commit(".."));
+    let base_path = Path::new("..");
+    for path in commit::watched_paths(base_path) {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+    // a missing commit is not worth failing the codegen build for
+    let commit = commit::resolve_head(base_path, None).map(|commit| commit.id).unwrap_or_else(|e| {
+        println!("cargo:warning={}", e);
+        "unknown".to_owned()
+    });
+    println!("cargo:rustc-env=GIT_HASH={}", commit);
 }
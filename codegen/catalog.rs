@@ -0,0 +1,99 @@
+use serde::Serialize;
+use crate::ByteString;
+
+/// bumped on every breaking change to the format of `tools.json`
+pub const VERSION: u32 = 1;
+
+/// `tools.json` in the dest, the public catalog of every tool
+///
+/// The descriptions and notices are html, as on the site. The link and icon urls are absolute,
+/// with the scheme of the environment, except for icons under a relative asset base.
+#[derive(Debug, Serialize)]
+pub struct Catalog<'a> {
+    pub version: u32,
+    pub commit: &'a str,
+    pub tools: &'a [Tool],
+}
+
+/// `mirror-manifest.json` in the dest, every mirror key for the mirror sync tooling
+#[derive(Debug, Serialize)]
+pub struct MirrorManifest<'a> {
+    pub commit: &'a str,
+    pub mirrors: Vec<Mirror<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Mirror<'a> {
+    /// under `mirror/` in the redirect service
+    pub key: &'a str,
+    /// `active`, `locked`, `synced`, or `unknown` if not given in the data
+    #[serde(rename = "type")]
+    pub mirror_type: &'static str,
+    pub tool: &'a str,
+    pub tool_title: &'a str,
+    pub title: &'a str,
+}
+
+impl<'a> MirrorManifest<'a> {
+    pub fn new(commit: &'a str, tools: &'a [Tool]) -> MirrorManifest<'a> {
+        let mut mirrors = Vec::new();
+        for tool in tools {
+            for link in tool.links.iter().filter(|link| link.link_type == "mirror") {
+                let mirror_type = link.mirror.unwrap_or("unknown");
+                mirrors.push(Mirror { key: &link.key, mirror_type, tool: &tool.name, tool_title: &tool.title, title: &link.title });
+            }
+        }
+        MirrorManifest { commit, mirrors }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Tool {
+    pub name: ByteString,
+    pub title: ByteString,
+    /// the index group listing the tool, if any
+    pub group: Option<ByteString>,
+    pub categories: Vec<ByteString>,
+    pub icon: Option<ByteString>,
+    pub description: Option<ByteString>,
+    pub notice: Option<ByteString>,
+    /// `active`, `locked` or `synced`, if the tool has a mirror download
+    pub mirror: Option<&'static str>,
+    /// the reason, for discontinued tools
+    pub deprecated: Option<ByteString>,
+    pub replaced_by: Option<ByteString>,
+    /// left out of the pages, but its links still work
+    pub hidden: bool,
+    /// the former names
+    pub aliases: Vec<ByteString>,
+    pub links: Vec<Link>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Link {
+    pub title: ByteString,
+    /// the key under `link_type` in the redirect service, e.g. `dismxx-ghr`
+    pub key: ByteString,
+    pub url: ByteString,
+    /// `r2` or `mirror`
+    pub link_type: &'static str,
+    /// `link` or `download`
+    pub icon: &'static str,
+    /// `active`, `locked` or `synced`, for the mirror links
+    pub mirror: Option<&'static str>,
+    /// the details of the file, for the downloads given with them
+    pub download: Option<Download>,
+    /// the title of the downloads group, for the links in one
+    pub group: Option<ByteString>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Download {
+    pub version: Option<ByteString>,
+    /// in bytes
+    pub size: Option<u64>,
+    pub sha256: Option<ByteString>,
+    pub platforms: Vec<ByteString>,
+    /// `YYYY-MM-DD`
+    pub date: Option<ByteString>,
+}
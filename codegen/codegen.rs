@@ -2,7 +2,7 @@ use foundations::vec_ext;
 use lighthtml::{*, prelude::*};
 use crate::ByteString;
 type Node = lighthtml::Node<ByteString>;
-use crate::{s, util::*, config::*, data::*, Map, Inserts};
+use crate::{s, util::*, config::*, data::*, catalog, search::{self, WEIGHT_TITLE, WEIGHT_KEYWORDS, WEIGHT_DESCRIPTION, WEIGHT_NOTICE, WEIGHT_LINK_TITLES}, error::Errors, Map, Inserts};
 
 macro_rules! classes {
     ($($item:tt)+) => {
@@ -116,9 +116,9 @@ fn tile_inner(Tile { tile, font, action, icon_type, name, title, icon, path, sub
         TileAction::Category => call!("category"),
         TileAction::Copy => call!("copy"),
         TileAction::Path => link!(path.clone().unwrap_or_else(|| s!("/", name, "/"))),
-        TileAction::Subdomain => link!(s!("//", subdomain.as_ref().unwrap(), ".pc.wiki/")),
-        TileAction::R => link!(s!("//r.ldt.pc.wiki/r/", name, "/")),
-        TileAction::R2 => link!(s!("//r.ldt.pc.wiki/r2/", name, "/")),
+        TileAction::Subdomain => link!(s!("//", subdomain.as_ref().unwrap(), "{{SUBDOMAIN}}/")),
+        TileAction::R => link!(s!("{{REDIRECT}}/r/", name, "/")),
+        TileAction::R2 => link!(s!("{{REDIRECT}}/r2/", name, "/")),
         TileAction::None => none!(),
     }
 }
@@ -131,24 +131,34 @@ fn tile_columns(input: TileColumns) -> impl Iterator<Item = Node> {
     input.map(|o| Element(E_DIV, class!("tile-column"), o.map_to(tile)))
 }
 
-fn tile_grids(TileGrids { left, middle }: TileGrids) -> Vec<Node> {
-    let [
-        TileGridMiddle { title: first_title, content: first },
-        TileGridMiddle { title: second_title, content: second },
-        TileGridMiddle { title: third_title, content: third },
-    ]: [TileGridMiddle; 3] = middle.try_into().unwrap();
-    assert_eq!(third.len(), 9);
+fn tile_grids(TileGrids { sections }: TileGrids) -> Vec<Node> {
+    let mut vertical = Vec::new();
+    let mut middle = Vec::new();
+    let mut trailing = Vec::new();
+
+    for TileGridSection { placement, title, content, .. } in sections {
+        let title = title.map(|title| {
+            Element(E_DIV, class!(if middle.is_empty() { "title top" } else { "title" }), text!(title))
+        });
+        match placement {
+            TileGridPlacement::Vertical => vertical.push(Element(E_DIV, class!("tile-grid-vertical"), content.map_to(tile))),
+            TileGridPlacement::Middle => {
+                middle.extend(title);
+                middle.extend(content.map(tile));
+            },
+            TileGridPlacement::Trailing => {
+                middle.extend(title);
+                trailing.extend(content.map(tile));
+            },
+        }
+    }
 
     vec_ext![
-        Element(E_DIV, class!("tile-grid-vertical"), left.map_to(tile)),
-        Element(E_DIV, class!("tile-grid-middle"), vec_ext![
-            Element(E_DIV, class!("title top"), text!(first_title)),
-            @extend(first.map(tile)),
-            Element(E_DIV, class!("title"), text!(second_title)),
-            @extend(second.map(tile)),
-            Element(E_DIV, class!("title"), text!(third_title)),
-        ]),
-        @extend(third.map(tile)),
+        @extend(vertical),
+        @if (!middle.is_empty()) {
+            Element(E_DIV, class!("tile-grid-middle"), middle)
+        },
+        @extend(trailing),
     ]
 }
 
@@ -209,48 +219,149 @@ fn category_group(CategoryGroup { title, content }: CategoryGroup) -> Node {
     ])
 }
 
-fn category_tab(content: Vec<CategoryGroup>) -> Vec<Node> {
-    let mut content = content.map(category_group);
-    let mut left = Vec::new();
-    let mut right = Vec::new();
-    if let Some(l1) = content.next() { left.push(l1); }
-    if let Some(l2) = content.next() { left.push(l2); }
-    if let Some(r1) = content.next() { right.push(r1); }
-    if let Some(r2) = content.next() { right.push(r2); }
-    assert!(content.next().is_none());
-    vec![
-        Element(E_DIV, class!("category-tab-part"), left),
-        Element(E_DIV, class!("category-tab-part"), right),
-    ]
+/// splits the groups into columns of similar height, keeping their order
+fn category_tab(content: Vec<CategoryGroup>, columns: usize) -> Vec<Node> {
+    let columns = columns.max(1);
+    // a group is as high as its title and items
+    let group_height = |group: &CategoryGroup| group.content.len() + 1;
+    let mut rest: usize = content.iter().map(group_height).sum();
+    let mut target = rest.div_ceil(columns);
+    let mut parts = vec![Vec::new()];
+    let mut height = 0;
+    for group in content {
+        // the current column has its share of the rest
+        if height >= target && parts.len() < columns {
+            rest -= height;
+            parts.push(Vec::new());
+            height = 0;
+            target = rest.div_ceil(columns - parts.len() + 1);
+        }
+        height += group_height(&group);
+        parts.last_mut().unwrap().push(category_group(group));
+    }
+    parts.resize_with(columns, Vec::new);
+    parts.into_iter().map(|part| Element(E_DIV, class!("category-tab-part"), part)).collect()
 }
 
-fn category(Category { tool, link }: Category) -> Vec<Node> {
-    let CategoryTab { title: tool_title, content: tool } = tool;
-    let CategoryTab { title: link_title, content: link } = link;
+fn category(Category { columns, tabs }: Category) -> Vec<Node> {
+    let mut buttons = Vec::new();
+    let mut lists = Vec::new();
+    for (i, CategoryTab { title, content }) in tabs.into_iter().enumerate() {
+        // the first tab is shown initially
+        let selected = i == 0;
+        buttons.push(Element(E_DIV, attr!{
+            A_ID: s!("category-button-", i.to_string()),
+            A_CLASS: s!("category-button", if selected { " selected" } else { "" }),
+        }, text!(title)));
+        lists.push(Element(E_DIV, vec_ext![
+            (A_ID, s!("category-list-", i.to_string())),
+            (A_CLASS, s!("category-list")),
+            @if (!selected) { (A_STYLE, s!("opacity: 0; pointer-events: none")) },
+        ], category_tab(content, columns)));
+    }
     vec![
-        Element(E_DIV, class!("category-title"), vec![
-            Element(E_DIV, attr!{A_ID: s!("tool-button"), A_CLASS: s!("selected")}, text!(tool_title)),
-            Element(E_DIV, id!("link-button"), text!(link_title)),
-        ]),
-        Element(E_DIV, class!("category-content"), vec![
-            Element(E_DIV, id!("tool-list"), category_tab(tool)),
-            Element(E_DIV, attr!{A_ID: s!("link-list"), A_STYLE: s!("opacity: 0; pointer-events: none")}, category_tab(link)),
-        ])
+        Element(E_DIV, class!("category-title"), buttons),
+        Element(E_DIV, class!("category-content"), lists),
     ]
 }
 
-fn tool_groups(mut groups: Vec<ToolGroup>, major_category: Category) -> (Map<Tool>, ToolData) {
+fn catalog_link(ToolLink { title, link_type, link, icon, mirror, download }: &ToolLink, group: Option<&ByteString>) -> catalog::Link {
+    catalog::Link {
+        title: tool_link_title(title.clone()),
+        key: link.clone(),
+        url: s!(tool_link_prefix(*link_type), link),
+        link_type: link_type.as_str(),
+        icon: icon.as_str(),
+        mirror: mirror.map(|mirror| mirror.as_str()),
+        download: download.as_ref().map(|ToolDownloadInfo { version, size, sha256, platforms, date }| catalog::Download {
+            version: version.clone(),
+            size: *size,
+            sha256: sha256.clone(),
+            platforms: platforms.clone(),
+            date: date.clone(),
+        }),
+        group: group.cloned(),
+    }
+}
+
+/// the tool names by their `aliases`
+fn tool_aliases(groups: &[ToolGroup]) -> Map<ByteString> {
+    let mut res = Map::new();
+    for tool in groups.iter().flat_map(|group| &group.list) {
+        for alias in tool.aliases.iter().flatten() {
+            // duplicates are reported by `validate::validate`
+            let _ = res.entry(alias.clone()).or_insert_with(|| tool.name.clone());
+        }
+    }
+    res
+}
+
+fn resolve_alias(aliases: &Map<ByteString>, name: &mut ByteString) {
+    if let Some(tool_name) = aliases.get(name) {
+        *name = tool_name.clone();
+    }
+}
+
+fn resolve_tile_aliases<'a>(aliases: &Map<ByteString>, tiles: impl IntoIterator<Item = &'a mut Tile>) {
+    for tile in tiles {
+        if matches!(tile.action, TileAction::Tool) {
+            resolve_alias(aliases, &mut tile.name);
+        }
+    }
+}
+
+/// rewrites the references to a tool (or the tool group named after it) by one of its `aliases` to its name,
+/// before validating, so that nothing after has to know about the aliases
+fn resolve_aliases(aliases: &Map<ByteString>, groups: &mut [ToolGroup], major: &mut TileGrids, sides: &mut [Side], category: &mut Category) {
+    if aliases.is_empty() {
+        return;
+    }
+    for tool in groups.iter_mut().flat_map(|group| &mut group.list) {
+        for name in tool.cross.iter_mut().chain(&mut tool.cross_top).flatten().chain(&mut tool.replaced_by) {
+            resolve_alias(aliases, name);
+        }
+        if let Some(cross_notice) = &mut tool.cross_notice {
+            *cross_notice = cross_notice.drain(..).map(|(mut name, notice)| {
+                resolve_alias(aliases, &mut name);
+                (name, notice)
+            }).collect();
+        }
+    }
+    resolve_tile_aliases(aliases, major.sections.iter_mut().flat_map(|section| &mut section.content));
+    resolve_tile_aliases(aliases, category.tabs.iter_mut().flat_map(|tab| &mut tab.content).flat_map(|group| &mut group.content));
+    for side in sides {
+        resolve_tile_aliases(aliases, side.tiles.iter_mut().flatten());
+        if let Some(TileTemplate { template, tiles }) = &mut side.templated {
+            if matches!(template.action, TileAction::Tool) {
+                match tiles {
+                    TileTemplateTiles::WithoutTitle(names) => {
+                        for name in names {
+                            resolve_alias(aliases, name);
+                        }
+                    },
+                    TileTemplateTiles::WithTitle(names) => {
+                        *names = names.drain(..).map(|(mut name, title)| {
+                            resolve_alias(aliases, &mut name);
+                            (name, title)
+                        }).collect();
+                    },
+                }
+            }
+        }
+    }
+}
+
+// every reference here is already checked by `validate::validate`
+fn tool_groups(mut groups: Vec<ToolGroup>, major_category: Category) -> (Map<Tool>, ToolData, Vec<catalog::Tool>) {
     let mut tools = Map::new();
     let mut index = Map::new();
-    let mut all = Map::new();
+    let mut search = Vec::new();
+    let mut catalog = Vec::new();
     let mut cross = Map::new();
     let mut cross_notice_title: Map<ByteString> = Map::new();
     let mut category = Map::new();
 
-    for tab in {
-        let Category { tool, link } = major_category;
-        [tool, link]
-    } {
+    for tab in major_category.tabs {
         for group in tab.content {
             for item in group.content {
                 if matches!(item.action, TileAction::Category) {
@@ -285,9 +396,51 @@ fn tool_groups(mut groups: Vec<ToolGroup>, major_category: Category) -> (Map<Too
         let mut list = Vec::new();
         for tool in &mut group.list {
             tool.no_icon.or_self(group.no_icon);
-            list.push(tool.name.clone());
-            all.first_insert(s!(tool.title, tool.keywords.clone().unwrap_or_default()), tool.name.clone());
+            let hidden = tool.hidden.unwrap_or(false);
+            let link_sections = resolve_links(&tool.name, &tool.links);
+            let ToolLinkSections { links, downloads, downloads_groups, tile_links } = &link_sections;
+            catalog.push(catalog::Tool {
+                name: tool.name.clone(),
+                title: tool.title.clone(),
+                group: (group_name != "non-index").then(|| group_name.clone()),
+                categories: tool.category.clone().unwrap_or_default(),
+                icon: (!tool.no_icon.unwrap_or(false)).then(|| {
+                    s!("{{ASSERT}}/image/icon-tool/", tool.icon.as_ref().unwrap_or(&tool.name), ".webp")
+                }),
+                description: tool.description.clone(),
+                notice: tool.notice.clone(),
+                mirror: tool.links.mirror.map(|mirror| mirror.as_str()),
+                deprecated: tool.deprecated.clone(),
+                replaced_by: tool.replaced_by.clone(),
+                hidden,
+                aliases: tool.aliases.clone().unwrap_or_default(),
+                links: links.iter().chain(downloads).chain(tile_links).map(|link| catalog_link(link, None))
+                    .chain(downloads_groups.iter().flat_map(|(title, group)| group.iter().map(move |link| catalog_link(link, Some(title)))))
+                    .collect(),
+            });
+            // the template is still generated, so the tool can be linked to by name
             tools.first_insert(tool.name.clone(), tool.clone());
+            if hidden {
+                continue;
+            }
+            list.push(tool.name.clone());
+            // the typed titles (官方网站 ...) are shared by most tools, so only the written ones are searched
+            let link_titles = link_sections.iter().filter_map(|link| match &link.title {
+                ToolLinkTitle::Text(title) => Some(title.as_str()),
+                ToolLinkTitle::Type(_) => None,
+            });
+            search.push(ToolSearchRecord {
+                name: tool.name.clone(),
+                group: (group_name != "non-index").then(|| group_name.clone()),
+                categories: tool.category.clone().unwrap_or_default(),
+                fields: [
+                    search::field(WEIGHT_TITLE, [tool.title.as_str()]),
+                    search::field(WEIGHT_KEYWORDS, tool.keywords.as_deref().into_iter().chain(tool.aliases.iter().flatten().map(|alias| alias.as_str()))),
+                    search::field(WEIGHT_DESCRIPTION, tool.description.as_deref()),
+                    search::field(WEIGHT_NOTICE, tool.notice.as_deref()),
+                    search::field(WEIGHT_LINK_TITLES, link_titles.chain(link_sections.downloads_groups.iter().map(|(title, _)| title.as_str()))),
+                ].into_iter().flatten().collect(),
+            });
             if let Some(cross_notice) = &tool.cross_notice {
                 for (notice_group, notice) in cross_notice {
                     cross.get_mut(notice_group).unwrap().first_insert(
@@ -317,7 +470,7 @@ fn tool_groups(mut groups: Vec<ToolGroup>, major_category: Category) -> (Map<Too
     }
 
     for group in &groups {
-        for tool in &group.list {
+        for tool in group.list.iter().filter(|tool| !tool.hidden.unwrap_or(false)) {
             if let Some(cross) = &tool.cross {
                 for cross_group_name in cross {
                     index.get_mut(cross_group_name).unwrap().cross_list.push(tool.name.clone())
@@ -331,18 +484,46 @@ fn tool_groups(mut groups: Vec<ToolGroup>, major_category: Category) -> (Map<Too
         }
     }
 
-    (tools, ToolData { index, all, cross, category })
+    (tools, ToolData { index, search, cross, category }, catalog)
 }
 
-fn tool_link_title(title: ToolLinkTitle) -> ByteString {
+pub fn tool_link_title(title: ToolLinkTitle) -> ByteString {
     match title {
         ToolLinkTitle::Text(title) => title,
         ToolLinkTitle::Type(t) => s!(tool_website_type(t)),
     }
 }
 
-fn tool_link(ToolLink { title, link_type, link, icon }: ToolLink) -> Node {
-    Element(E_SPAN, vec![], vec![
+/// formats a file size with binary units, e.g. `24.3 MB`
+fn file_size(size: u64) -> ByteString {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if size < 1024 {
+        return s!(size.to_string(), " B");
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    s!(format!("{value:.1} "), UNITS[unit])
+}
+
+/// the version, size, platforms and release date of a download, as shown after its link
+fn download_summary(ToolDownloadInfo { version, size, platforms, date, .. }: &ToolDownloadInfo) -> Option<ByteString> {
+    let parts: Vec<_> = vec_ext![
+        @extend(version.clone()),
+        @extend(size.map(file_size)),
+        @if (!platforms.is_empty()) {
+            s!(platforms.join("/"))
+        },
+        @extend(date.clone()),
+    ];
+    (!parts.is_empty()).then(|| s!(parts.join(" · ")))
+}
+
+fn tool_link(ToolLink { title, link_type, link, icon, mirror, download }: ToolLink) -> Node {
+    Element(E_SPAN, vec![], vec_ext![
         Element(E_A, attr!{
             A_TARGET: s!("_blank"),
             A_CLASS: s!("link"),
@@ -351,12 +532,25 @@ fn tool_link(ToolLink { title, link_type, link, icon }: ToolLink) -> Node {
             svg_icon!(icon.as_str()),
             nbsp!(),
             Text(tool_link_title(title)),
-        ])
+        ]),
+        @if let (Some(mirror) = mirror) {
+            Element(E_SPAN, class!("mirror-badge mirror-", mirror.as_str()), text!(s!(mirror_type_label(mirror))))
+        },
+        @if let (Some(download) = download) {
+            Element(E_SPAN, class!("download-info"), vec_ext![
+                @extend(download_summary(&download).map(Text)),
+                @if let (Some(sha256) = download.sha256) {
+                    Element(E_SPAN, class!("download-sha256"), text!(s!("SHA-256 ", sha256)))
+                },
+            ])
+        },
     ])
 }
 
-fn tool_link_plain(ToolLink { title, link_type, link, icon }: ToolLink) -> Node {
-    Element(E_SPAN, attr!{}, vec![
+fn tool_link_plain(ToolLink { title, link_type, link, icon, mirror, download }: ToolLink) -> Node {
+    let summary = download.as_ref().and_then(download_summary);
+    let sha256 = download.and_then(|download| download.sha256);
+    Element(E_SPAN, attr!{}, vec_ext![
         Element(E_A, attr!{
             A_TARGET: s!("_blank"),
             A_HREF: s!(tool_link_prefix(link_type), link),
@@ -365,113 +559,171 @@ fn tool_link_plain(ToolLink { title, link_type, link, icon }: ToolLink) -> Node
         ]),
         nbsp!(),
         Element(E_I, attr!{}, text!(s!("[", link_type.as_str(), "] ", link))),
+        @if (mirror.is_some()) {
+            nbsp!()
+        },
+        @if let (Some(mirror) = mirror) {
+            Element(E_I, class!("hint"), text!(s!("[", mirror.as_str(), "]")))
+        },
+        @if (summary.is_some()) {
+            nbsp!()
+        },
+        @if let (Some(summary) = summary) {
+            Element(E_I, class!("hint"), text!(s!("[", summary, "]")))
+        },
+        @if (sha256.is_some()) {
+            empty!(E_BR)
+        },
+        @if let (Some(sha256) = sha256) {
+            Element(E_I, class!("hint"), text!(s!("SHA-256 ", sha256)))
+        },
         empty!(E_BR),
     ])
 }
 
-fn tool_links(name: ByteString, ToolLinks { website, websites, websites_tile, websites_tile_template, downloads, downloads_groups, mirror, mirrors, columns }: ToolLinks, plain: bool) -> Vec<Node> {
-    let attrs = (!plain && columns.unwrap_or(false)).then(|| (A_CLASS, s!("tool-links-columns"))).to_vec();
-    let tool_link_selected: fn(ToolLink) -> lighthtml::Node<String> = if plain { tool_link_plain } else { tool_link };
-    let mut res = Vec::new();
+/// every link of a tool, grouped as they are rendered
+#[derive(Clone, Debug)]
+pub struct ToolLinkSections {
+    pub links: Vec<ToolLink>,
+    pub downloads: Vec<ToolLink>,
+    pub downloads_groups: Vec<(ByteString, Vec<ToolLink>)>,
+    pub tile_links: Vec<ToolLink>,
+}
 
-    {
-        let mut res_links = Vec::new();
-        if let Some(website) = website {
-            res_links.push(ToolLink {
-                title: website,
+impl ToolLinkSections {
+    pub fn iter(&self) -> impl Iterator<Item = &ToolLink> {
+        self.links.iter()
+            .chain(&self.downloads)
+            .chain(self.downloads_groups.iter().flat_map(|(_, group)| group))
+            .chain(&self.tile_links)
+    }
+}
+
+/// applies the naming convention of the redirect keys (`r2/{name}`, `r2/{name}-d-{link}`, `mirror/{name}` ...)
+pub fn resolve_links(name: &str, ToolLinks { website, websites, websites_tile, downloads, downloads_groups, mirror, mirrors, .. }: &ToolLinks) -> ToolLinkSections {
+    let mut links = Vec::new();
+    if let Some(website) = website {
+        links.push(ToolLink {
+            title: website.clone(),
+            link_type: ToolLinkType::R2,
+            link: s!(name),
+            icon: ToolLinkIcon::Link,
+            mirror: None,
+            download: None,
+        });
+    }
+    if let Some(websites) = websites {
+        for (link, title) in websites {
+            links.push(ToolLink {
+                title: title.clone(),
                 link_type: ToolLinkType::R2,
-                link: name.clone(),
+                link: s!(name, "-", link),
                 icon: ToolLinkIcon::Link,
+                mirror: None,
+                download: None,
             });
         }
-        if let Some(websites) = websites {
-            for (link, title) in websites {
-                res_links.push(ToolLink {
-                    title,
-                    link_type: ToolLinkType::R2,
-                    link: s!(name, "-", link),
-                    icon: ToolLinkIcon::Link,
-                });
-            }
-        }
-        if !res_links.is_empty() {
-            res.push(Element(if plain { E_P } else { E_DIV }, attrs.clone(), res_links.map_to(tool_link_selected)));
-        }
     }
 
-    {
-        let mut res_downloads = Vec::new();
-        if let Some(downloads) = downloads {
-            for (link, title) in downloads {
-                res_downloads.push(ToolLink {
-                    title: ToolLinkTitle::Text(title),
-                    link_type: ToolLinkType::R2,
-                    link: s!(name, "-d-", link),
-                    icon: ToolLinkIcon::Download,
-                });
-            }
+    let mut res_downloads = Vec::new();
+    if let Some(downloads) = downloads {
+        for (link, entry) in downloads {
+            res_downloads.push(ToolLink {
+                title: ToolLinkTitle::Text(entry.title().clone()),
+                link_type: ToolLinkType::R2,
+                link: s!(name, "-d-", link),
+                icon: ToolLinkIcon::Download,
+                mirror: None,
+                download: entry.info().cloned(),
+            });
         }
-        if let Some(_mirror) = mirror {
+    }
+    if let Some(mirror) = mirror {
+        res_downloads.push(ToolLink {
+            title: ToolLinkTitle::Text(s!("镜像下载")),
+            link_type: ToolLinkType::Mirror,
+            link: s!(name),
+            icon: ToolLinkIcon::Download,
+            mirror: Some(*mirror),
+            download: None,
+        })
+    }
+    if let Some(mirrors) = mirrors {
+        for (link, entry) in mirrors {
             res_downloads.push(ToolLink {
-                title: ToolLinkTitle::Text(s!("镜像下载")),
+                title: ToolLinkTitle::Text(entry.title().clone()),
                 link_type: ToolLinkType::Mirror,
-                link: name.clone(),
+                link: s!(name, "-", link),
                 icon: ToolLinkIcon::Download,
-            })
-        }
-        if let Some(mirrors) = mirrors {
-            for (link, title) in mirrors {
-                res_downloads.push(ToolLink {
-                    title: ToolLinkTitle::Text(title),
-                    link_type: ToolLinkType::Mirror,
-                    link: s!(name, "-", link),
-                    icon: ToolLinkIcon::Download,
-                });
-            }
-        }
-        if !res_downloads.is_empty() {
-            res.push(Element(if plain { E_P } else { E_DIV }, attrs.clone(), res_downloads.map_to(tool_link_selected)));
+                // none if neither the entry nor the tool gives a state
+                mirror: entry.mirror_type().or(*mirror),
+                download: None,
+            });
         }
     }
 
+    let mut res_downloads_groups = Vec::new();
     if let Some(downloads_groups) = downloads_groups {
         for (group_title, downloads_group) in downloads_groups {
-            let mut res_group = Vec::new();
-            res_group.push(Element(E_P, attr!{}, vec![Element(E_B, attr!{}, text!(s!(group_title)))]));
-            for (link, title) in downloads_group {
-                res_group.push(tool_link_selected(ToolLink {
-                    title: ToolLinkTitle::Text(title),
-                    link_type: ToolLinkType::R2,
-                    link: s!(name, "-d-", link),
-                    icon: ToolLinkIcon::Download,
-                }));
-            }
-            res.push(Element(if plain { E_P } else { E_DIV }, attr!{}, res_group));
+            res_downloads_groups.push((group_title.clone(), downloads_group.iter().map(|(link, entry)| ToolLink {
+                title: ToolLinkTitle::Text(entry.title().clone()),
+                link_type: ToolLinkType::R2,
+                link: s!(name, "-d-", link),
+                icon: ToolLinkIcon::Download,
+                mirror: None,
+                download: entry.info().cloned(),
+            }).collect()));
         }
     }
 
-    {
-        let mut tile_links = Vec::new();
-        if let Some(websites_tile) = websites_tile {
-            for (link, title) in websites_tile {
-                tile_links.push(ToolLink {
-                    title,
-                    link_type: ToolLinkType::R2,
-                    link: s!(name, "-", link),
-                    icon: ToolLinkIcon::Link,
-                });
-            }
+    let mut tile_links = Vec::new();
+    if let Some(websites_tile) = websites_tile {
+        for (link, title) in websites_tile {
+            tile_links.push(ToolLink {
+                title: title.clone(),
+                link_type: ToolLinkType::R2,
+                link: s!(name, "-", link),
+                icon: ToolLinkIcon::Link,
+                mirror: None,
+                download: None,
+            });
         }
-        if !tile_links.is_empty() {
-            if plain {
-                res.push(Element(E_P, attrs, tile_links.map_to(tool_link_plain)));
-            } else {
-                res.extend(tile_template(TileTemplate {
-                    template: websites_tile_template.unwrap(),
-                    tiles: TileTemplateTiles::WithoutTitle(tile_links.map_to(|ToolLink { link, .. }| link))
-                }).map(tile));
-                res.push(clearfix!())
-            }
+    }
+
+    ToolLinkSections { links, downloads: res_downloads, downloads_groups: res_downloads_groups, tile_links }
+}
+
+fn tool_links(name: ByteString, links: ToolLinks, plain: bool) -> Vec<Node> {
+    let ToolLinkSections { links: res_links, downloads: res_downloads, downloads_groups, tile_links } = resolve_links(&name, &links);
+    let ToolLinks { websites_tile_template, columns, .. } = links;
+    let attrs = (!plain && columns.unwrap_or(false)).then(|| (A_CLASS, s!("tool-links-columns"))).to_vec();
+    let tool_link_selected: fn(ToolLink) -> lighthtml::Node<String> = if plain { tool_link_plain } else { tool_link };
+    let mut res = Vec::new();
+
+    if !res_links.is_empty() {
+        res.push(Element(if plain { E_P } else { E_DIV }, attrs.clone(), res_links.map_to(tool_link_selected)));
+    }
+
+    if !res_downloads.is_empty() {
+        res.push(Element(if plain { E_P } else { E_DIV }, attrs.clone(), res_downloads.map_to(tool_link_selected)));
+    }
+
+    for (group_title, downloads_group) in downloads_groups {
+        let mut res_group = Vec::new();
+        res_group.push(Element(E_P, attr!{}, vec![Element(E_B, attr!{}, text!(s!(group_title)))]));
+        res_group.extend(downloads_group.map(tool_link_selected));
+        res.push(Element(if plain { E_P } else { E_DIV }, attr!{}, res_group));
+    }
+
+    if !tile_links.is_empty() {
+        if plain {
+            res.push(Element(E_P, attrs, tile_links.map_to(tool_link_plain)));
+        } else {
+            res.extend(tile_template(TileTemplate {
+                template: websites_tile_template.unwrap(),
+                tiles: TileTemplateTiles::WithoutTitle(tile_links.map_to(|ToolLink { link, .. }| link))
+            }).map(tile));
+            res.push(clearfix!())
         }
     }
 
@@ -486,7 +738,18 @@ fn tool_notice(notice: ByteString) -> Node {
     ])
 }
 
-fn tool(Tool { name, title, icon, description, notice, links, no_icon, .. }: Tool) -> Node {
+/// the notice of a deprecated tool, with the link to its replacement
+fn tool_deprecated(reason: ByteString, replacement: Option<Node>) -> Node {
+    Element(E_P, class!("deprecated"), vec_ext![
+        Element(E_B, attr!{}, text!(s!("已停止维护"))),
+        empty!(E_BR),
+        Text(reason),
+        @extend(replacement.map(|link| [empty!(E_BR), Text(s!("替代工具：")), link]).into_iter().flatten()),
+    ])
+}
+
+/// `replacement` is the tool group to open and the title of the tool replacing a deprecated one
+fn tool(Tool { name, title, icon, description, notice, links, no_icon, deprecated, .. }: Tool, replacement: Option<(ByteString, ByteString)>) -> Node {
     Element(E_TEMPLATE, id!("tool-", name), vec![
         Element(E_DIV, attr!{
             A_CLASS: s!("item"),
@@ -499,12 +762,21 @@ fn tool(Tool { name, title, icon, description, notice, links, no_icon, .. }: Too
                         A_ALT: title.clone(),
                     ], vec![])
                 },
-                Text(title)
+                Text(title),
+                @if (deprecated.is_some()) {
+                    Element(E_SPAN, class!("deprecated-badge"), text!(s!("已停更")))
+                },
             ]),
             svg_icon!("expand-right", "icon-line"),
             Element(E_DIV, class!("detail-container"), vec![
                 Element(E_DIV, class!("detail"), vec_ext![
                     Element(E_P, attr!{}, description.map(Text).to_vec()),
+                    @if let (Some(reason) = deprecated) {
+                        // not to fold the detail when switching to the replacement
+                        tool_deprecated(reason, replacement.map(|(group, title)| {
+                            Element(E_A, attr!{A_ONCLICK: s!("event.stopPropagation(); tool('", group, "')")}, text!(title))
+                        }))
+                    },
                     @append(&mut tool_links(name.clone(), links, false)),
                     @if let (Some(notice) = notice) {
                         tool_notice(notice)
@@ -538,9 +810,18 @@ impl CrossType {
     }
 }
 
-fn tool_plain(Tool { name, title, description, notice, links, .. }: Tool, cross: CrossType, has_title: bool) -> Vec<Node> {
+/// keeps the links to the former names of a tool working
+fn alias_anchors(aliases: Option<Vec<ByteString>>) -> Vec<Node> {
+    aliases.unwrap_or_default().map_to(|alias| Element(E_A, id!(alias), vec![]))
+}
+
+/// `replacement` is the name and the title of the tool replacing a deprecated one
+fn tool_plain(Tool { name, title, description, notice, links, deprecated, aliases, .. }: Tool, replacement: Option<(ByteString, ByteString)>, cross: CrossType, has_title: bool) -> Vec<Node> {
     let is_cross = cross.is_cross();
+    let is_deprecated = deprecated.is_some();
+    let anchors = if has_title { alias_anchors(aliases) } else { Vec::new() };
     vec_ext![
+        @extend(anchors),
         @if (has_title) {
             Element(E_H3, id!(name.clone()), vec_ext![
                 Text(s!(title)),
@@ -552,9 +833,20 @@ fn tool_plain(Tool { name, title, description, notice, links, .. }: Tool, cross:
                 @if (is_cross) {
                     Element(E_I, class!("hint"), text!(s!(cross.cross_sign())))
                 },
+                @if (is_deprecated) {
+                    nbsp!()
+                },
+                @if (is_deprecated) {
+                    Element(E_I, class!("hint"), text!(s!("[deprecated]")))
+                },
             ])
         },
         Element(E_P, attr!{}, description.map(Text).to_vec()),
+        @if let (Some(reason) = deprecated) {
+            tool_deprecated(reason, replacement.map(|(name, title)| {
+                Element(E_A, attr!{A_HREF: s!("#", name)}, text!(title))
+            }))
+        },
         @append(&mut tool_links(name, links, true)),
         @if let (Some(notice) = notice) {
             tool_notice(notice)
@@ -563,8 +855,17 @@ fn tool_plain(Tool { name, title, description, notice, links, .. }: Tool, cross:
 }
 
 fn tools_plain(tools: Map<Tool>, index: ToolIndex, cross: ToolCross) -> Vec<Node> {
+    let tool_plain = |tool_name: &ByteString, cross: CrossType, has_title: bool| {
+        let tool = tools.get(tool_name).unwrap().clone();
+        let replacement = tool.replaced_by.as_ref().map(|name| (name.clone(), tools.get(name).unwrap().title.clone()));
+        tool_plain(tool, replacement, cross, has_title)
+    };
     let mut res = Vec::new();
     for (name, ToolIndexItem { single, title, list, cross_list, cross_top_list }) in index {
+        let single_deprecated = single && tools.get(&list[0]).unwrap().deprecated.is_some();
+        if single {
+            res.extend(alias_anchors(tools.get(&list[0]).unwrap().aliases.clone()));
+        }
         res.push(Element(E_H2, id!(name.clone()), vec_ext![
             Text(s!(title, " ")),
             Element(E_I, attr!{}, text!(name.clone())),
@@ -576,20 +877,26 @@ fn tools_plain(tools: Map<Tool>, index: ToolIndex, cross: ToolCross) -> Vec<Node
             @if (single) {
                 nbsp!()
             },
+            @if (single_deprecated) {
+                Element(E_I, class!("hint"), text!(s!("[deprecated]")))
+            },
+            @if (single_deprecated) {
+                nbsp!()
+            },
             Element(E_A, attr!{A_CLASS: s!("toc"), A_HREF: s!("#toc")}, text!(s!("[目录]"))),
         ]));
         if single {
-            res.append(&mut tool_plain(tools.get(&list[0]).unwrap().clone(), CrossType::None, false));
+            res.append(&mut tool_plain(&list[0], CrossType::None, false));
         } else {
             for tool_name in cross_top_list {
-                res.append(&mut tool_plain(tools.get(&tool_name).unwrap().clone(), CrossType::CrossTop, true));
+                res.append(&mut tool_plain(&tool_name, CrossType::CrossTop, true));
             }
             for tool_name in list {
-                res.append(&mut tool_plain(tools.get(&tool_name).unwrap().clone(), CrossType::None, true));
+                res.append(&mut tool_plain(&tool_name, CrossType::None, true));
             }
         }
         for tool_name in cross_list {
-            res.append(&mut tool_plain(tools.get(&tool_name).unwrap().clone(), CrossType::Cross, true));
+            res.append(&mut tool_plain(&tool_name, CrossType::Cross, true));
             if let Some(cross_notice) = cross.get(&name).and_then(|m| m.get(&tool_name)) {
                 res.push(Text(cross_notice.clone()));
             }
@@ -598,6 +905,36 @@ fn tools_plain(tools: Map<Tool>, index: ToolIndex, cross: ToolCross) -> Vec<Node
     res
 }
 
+/// the tool group opened for each listed tool, preferring the one it belongs to over the ones it's crossed to
+fn tool_group_names(index: &ToolIndex) -> Map<ByteString> {
+    let mut res = Map::new();
+    for (name, ToolIndexItem { list, .. }) in index {
+        for tool_name in list {
+            let _ = res.entry(tool_name.clone()).or_insert_with(|| name.clone());
+        }
+    }
+    for (name, ToolIndexItem { cross_list, cross_top_list, .. }) in index {
+        for tool_name in cross_top_list.iter().chain(cross_list) {
+            let _ = res.entry(tool_name.clone()).or_insert_with(|| name.clone());
+        }
+    }
+    res
+}
+
+/// the ids given by `tools_plain_toc` and `tools_plain`, in page order, but the anchors of the aliases
+fn tools_plain_anchors(index: &ToolIndex) -> Vec<ByteString> {
+    let mut anchors = vec![s!("toc")];
+    for (name, ToolIndexItem { single, list, cross_list, cross_top_list, .. }) in index {
+        let tools = if *single { cross_list.iter().collect::<Vec<_>>() } else { cross_top_list.iter().chain(list).chain(cross_list).collect() };
+        for anchor in [name].into_iter().chain(tools) {
+            if !anchors.contains(anchor) {
+                anchors.push(anchor.clone());
+            }
+        }
+    }
+    anchors
+}
+
 fn tools_plain_toc(groups: Vec<ToolGroup>) -> Vec<Node> {
     let mut res = Vec::new();
     res.push(Element(E_H2, id!("toc"), text!(s!("目录"))));
@@ -625,7 +962,7 @@ fn classic_button(ClassicButton { target, text }: ClassicButton, top: bool) -> N
             },
         ),
         @if (target.is_some()) {
-            (A_HREF, s!("//r.ldt.pc.wiki/r/", target.as_ref().unwrap()))
+            (A_HREF, s!("{{REDIRECT}}/r/", target.as_ref().unwrap()))
         },
     ], text!(text))])
 }
@@ -658,22 +995,42 @@ fn classic(nodes: Vec<ClassicRootNode>) -> Vec<Node> {
     res
 }
 
-pub fn codegen<P: AsRef<std::path::Path>>(inserts: &mut Inserts, includes: &mut Map<GlobalData>, page_path: P) {
+pub fn codegen<P: AsRef<std::path::Path>>(inserts: &mut Inserts, includes: &mut Map<GlobalData>, anchors: &mut Map<Vec<ByteString>>, catalog: &mut Vec<catalog::Tool>, page_path: P) -> Result<(), Errors> {
     let page_path = page_path.as_ref();
+    let mut errors = Errors::new();
+    // loads every file before bailing out, so that all broken files are reported at once
     macro_rules! load {
-        ($s:literal -> $t:ty) => {{
-            let v: $t = serde_yaml::from_reader(std::fs::File::open(page_path.join($s)).unwrap()).unwrap(); v
-        }};
+        ($($v:ident = $s:literal -> $t:ty;)*) => {
+            $(let $v = errors.collect(crate::load_yaml::<$t, _>(page_path.join($s)));)*
+            errors.check()?;
+            $(let $v = $v.unwrap();)*
+        };
+    }
+
+    load! {
+        public_sides = "public/sides.yml" -> Vec<Side>;
+        home_major = "home/major.yml" -> TileColumns;
+        home_sides = "home/sides.yml" -> Vec<Side>;
+        tools_major = "tool/major.yml" -> TileGrids;
+        tools_sides = "tool/sides.yml" -> Vec<Side>;
+        tools_tools = "tool/tools.yml" -> Vec<ToolGroup>;
+        tools_category = "tool/category.yml" -> Category;
+        legacy_buttons = "legacy/buttons.yml" -> Vec<ClassicRootNode>;
     }
 
-    let public_sides = load!("public/sides.yml" -> Vec<Side>);
-    let home_major = load!("home/major.yml" -> TileColumns);
-    let home_sides = load!("home/sides.yml" -> Vec<Side>);
-    let tools_major = load!("tool/major.yml" -> TileGrids);
-    let tools_sides = load!("tool/sides.yml" -> Vec<Side>);
-    let tools_tools = load!("tool/tools.yml" -> Vec<ToolGroup>);
-    let tools_category = load!("tool/category.yml" -> Category);
-    let legacy_buttons = load!("legacy/buttons.yml" -> Vec<ClassicRootNode>);
+    let (mut tools_major, mut tools_sides, mut tools_tools, mut tools_category) = (tools_major, tools_sides, tools_tools, tools_category);
+    resolve_aliases(&tool_aliases(&tools_tools), &mut tools_tools, &mut tools_major, &mut tools_sides, &mut tools_category);
+
+    errors.extend(crate::validate::validate(page_path, crate::validate::Input {
+        public_sides: &public_sides,
+        home_major: &home_major,
+        home_sides: &home_sides,
+        tools_major: &tools_major,
+        tools_sides: &tools_sides,
+        tools_tools: &tools_tools,
+        tools_category: &tools_category,
+    }));
+    errors.check()?;
 
     let public_sides = public_sides.map(side);
 
@@ -682,14 +1039,22 @@ pub fn codegen<P: AsRef<std::path::Path>>(inserts: &mut Inserts, includes: &mut
     let mut home_sides = home_sides.map_to(side);
     home_sides.extend(public_sides.clone());
 
-    let (tools_ext, tool_data) = tool_groups(tools_tools.clone(), tools_category.clone());
+    let (tools_ext, tool_data, tools_catalog) = tool_groups(tools_tools.clone(), tools_category.clone());
+    *catalog = tools_catalog;
 
     let mut tools_fragments = tools_sides.map_to(side);
     tools_fragments.extend(public_sides);
-    tools_fragments.extend(tools_ext.values().cloned().map(tool));
+    let tool_group_names = tool_group_names(&tool_data.index);
+    tools_fragments.extend(tools_ext.values().cloned().map(|tool_item| {
+        let replacement = tool_item.replaced_by.as_ref().map(|name| {
+            (tool_group_names.get(name).unwrap().clone(), tools_ext.get(name).unwrap().title.clone())
+        });
+        tool(tool_item, replacement)
+    }));
     tools_fragments.push(major_fragment(tile_grids(tools_major), s!("tiles")));
     tools_fragments.push(major_fragment(category(tools_category), s!("category")));
 
+    anchors.first_insert(s!("tool-plain"), tools_plain_anchors(&tool_data.index));
     let mut tools_plains = tools_plain_toc(tools_tools);
     tools_plains.extend(tools_plain(tools_ext, tool_data.index.clone(), tool_data.cross.clone()));
 
@@ -739,4 +1104,52 @@ pub fn codegen<P: AsRef<std::path::Path>>(inserts: &mut Inserts, includes: &mut
 
     includes.first_insert(s!("ldt"), GlobalData::Home);
     includes.first_insert(s!("tool"), GlobalData::Tool { tool: tool_data });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn groups(sizes: &[usize]) -> Vec<CategoryGroup> {
+        sizes.iter().enumerate().map(|(i, &size)| CategoryGroup {
+            title: s!("group-", i.to_string()),
+            content: (0..size).map(|j| Tile {
+                tile: None,
+                font: None,
+                action: TileAction::Category,
+                icon_type: None,
+                name: s!("item-", i.to_string(), "-", j.to_string()),
+                title: Some(s!("item")),
+                icon: None,
+                path: None,
+                subdomain: None,
+            }).collect(),
+        }).collect()
+    }
+
+    /// the number of groups in each column
+    fn column_sizes(parts: Vec<Node>) -> Vec<usize> {
+        parts.into_iter().map(|part| match part {
+            Element(_, _, groups) => groups.len(),
+            _ => panic!("column is not an element"),
+        }).collect()
+    }
+
+    #[test]
+    fn category_tab_balances_columns() {
+        assert_eq!(column_sizes(category_tab(groups(&[3, 3, 3, 3]), 2)), [2, 2]);
+        assert_eq!(column_sizes(category_tab(groups(&[1, 1, 1, 1, 1, 1]), 3)), [2, 2, 2]);
+        // a high group fills its column alone
+        assert_eq!(column_sizes(category_tab(groups(&[5, 1, 1, 1]), 2)), [1, 3]);
+    }
+
+    #[test]
+    fn category_tab_pads_columns() {
+        assert_eq!(column_sizes(category_tab(groups(&[2]), 3)), [1, 0, 0]);
+        assert_eq!(column_sizes(category_tab(Vec::new(), 2)), [0, 0]);
+        // no columns are taken as one
+        assert_eq!(column_sizes(category_tab(groups(&[1, 1]), 0)), [2]);
+    }
 }
@@ -0,0 +1,239 @@
+// shared with build.rs, so only std is used here
+
+use std::{env, fs, path::{Path, PathBuf}, process::Command};
+
+/// overrides the content commit read from `.git`, e.g. for source tarballs (not the one embedded in the codegen)
+pub const COMMIT_ENV: &str = "LDTSTORE_COMMIT";
+
+pub struct Commit {
+    /// abbreviated to 7 digits
+    pub id: String,
+    /// whether the given paths have uncommitted changes (always `false` if overridden, not checked or git is not available)
+    pub dirty: bool,
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// `.git` itself, or the dir it links to in worktrees and submodules
+fn git_dir(base_path: &Path) -> Result<PathBuf, String> {
+    let dot_git = base_path.join(".git");
+    if dot_git.is_dir() {
+        return Ok(dot_git);
+    }
+    if dot_git.is_file() {
+        let link = read(&dot_git)?;
+        let dir = link.trim().strip_prefix("gitdir: ").ok_or_else(|| format!("{}: not a gitdir link", dot_git.display()))?;
+        return Ok(base_path.join(dir));
+    }
+    Err(format!("{} not found, give the commit with --commit or {}", dot_git.display(), COMMIT_ENV))
+}
+
+/// the refs live in the common dir, which differs from the git dir in worktrees
+fn common_dir(git_dir: &Path) -> PathBuf {
+    match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(dir) => git_dir.join(dir.trim()),
+        Err(_) => git_dir.to_owned(),
+    }
+}
+
+fn resolve_ref(git_dir: &Path, common_dir: &Path, name: &str) -> Result<String, String> {
+    for dir in [git_dir, common_dir] {
+        if let Ok(id) = fs::read_to_string(dir.join(name)) {
+            return Ok(id);
+        }
+    }
+    // moved here by `git gc` or `git pack-refs`
+    let packed_path = common_dir.join("packed-refs");
+    if let Ok(packed) = fs::read_to_string(&packed_path) {
+        for line in packed.lines() {
+            if line.starts_with('#') || line.starts_with('^') {
+                continue;
+            }
+            if let Some((id, ref_name)) = line.split_once(' ') {
+                if ref_name == name {
+                    return Ok(id.to_owned());
+                }
+            }
+        }
+    }
+    Err(format!("ref {} not found in {}", name, common_dir.display()))
+}
+
+fn head(base_path: &Path) -> Result<String, String> {
+    let git_dir = git_dir(base_path)?;
+    let common_dir = common_dir(&git_dir);
+    let head = read(&git_dir.join("HEAD"))?;
+    let head = head.trim();
+    match head.strip_prefix("ref: ") {
+        Some(name) => resolve_ref(&git_dir, &common_dir, name),
+        // detached
+        None => Ok(head.to_owned()),
+    }
+}
+
+fn abbrev(id: &str) -> Result<String, String> {
+    let id = id.trim();
+    if id.len() >= 7 && id.bytes().all(|b| b.is_ascii_hexdigit()) {
+        Ok(id[..7].to_ascii_lowercase())
+    } else {
+        Err(format!("malformed commit id `{}`", id))
+    }
+}
+
+fn dirty(base_path: &Path, paths: &[&str]) -> bool {
+    Command::new("git")
+        .arg("-C")
+        .arg(base_path)
+        .args(["status", "--porcelain", "--"])
+        .args(paths)
+        .output()
+        .map(|output| output.status.success() && !output.stdout.is_empty())
+        .unwrap_or(false)
+}
+
+/// the committer date of `id` in ISO 8601, if it can be found in the repo at `base_path`
+pub fn date(base_path: &Path, id: &str) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(base_path)
+        .args(["show", "-s", "--format=%cI", id])
+        .output()
+        .ok()?;
+    let date = String::from_utf8(output.stdout).ok()?;
+    let date = date.trim();
+    (output.status.success() && !date.is_empty()).then(|| date.to_owned())
+}
+
+/// the files that decide what `head` returns, limited to those that exist
+pub fn watched_paths(base_path: &Path) -> Vec<PathBuf> {
+    let dot_git = base_path.join(".git");
+    let Ok(git_dir) = git_dir(base_path) else {
+        return Vec::new();
+    };
+    let common_dir = common_dir(&git_dir);
+    [dot_git, git_dir.join("HEAD"), common_dir.join("refs"), common_dir.join("packed-refs")]
+        .into_iter()
+        .filter(|path| path.is_file() || (path.is_dir() && path != &git_dir))
+        .collect()
+}
+
+/// the HEAD of the repo at `base_path`, ignoring `COMMIT_ENV`
+///
+/// `paths` are checked for uncommitted changes; `None` skips the check.
+pub fn resolve_head(base_path: &Path, paths: Option<&[&str]>) -> Result<Commit, String> {
+    let id = abbrev(&head(base_path)?)?;
+    Ok(Commit { id, dirty: paths.is_some_and(|paths| dirty(base_path, paths)) })
+}
+
+/// the explicitly given commit, or the one in `COMMIT_ENV`, or the HEAD of the repo at `base_path`
+pub fn resolve(base_path: &Path, explicit: Option<&str>, paths: Option<&[&str]>) -> Result<Commit, String> {
+    if let Some(id) = explicit.map(ToOwned::to_owned).or_else(|| env::var(COMMIT_ENV).ok()) {
+        return Ok(Commit { id: abbrev(&id)?, dirty: false });
+    }
+    resolve_head(base_path, paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "0123456789abcdef0123456789abcdef01234567";
+    const OTHER_ID: &str = "fedcba9876543210fedcba9876543210fedcba98";
+
+    /// a fresh dir under the system temp dir, removed on drop
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let path = env::temp_dir().join(format!("ldtstore-commit-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+
+        fn write(&self, name: &str, content: &str) {
+            let path = self.0.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn loose_ref() {
+        let repo = TempDir::new("loose");
+        repo.write(".git/HEAD", "ref: refs/heads/main\n");
+        repo.write(".git/refs/heads/main", &format!("{}\n", ID));
+        repo.write(".git/packed-refs", &format!("{} refs/heads/main\n", OTHER_ID));
+        assert_eq!(head(&repo.0).unwrap().trim(), ID);
+    }
+
+    #[test]
+    fn packed_ref() {
+        let repo = TempDir::new("packed");
+        repo.write(".git/HEAD", "ref: refs/heads/main\n");
+        fs::create_dir_all(repo.0.join(".git/refs/heads")).unwrap();
+        repo.write(
+            ".git/packed-refs",
+            &format!("# pack-refs with: peeled fully-peeled sorted\n{} refs/heads/dev\n{} refs/heads/main\n^{}\n", OTHER_ID, ID, OTHER_ID),
+        );
+        assert_eq!(head(&repo.0).unwrap(), ID);
+    }
+
+    #[test]
+    fn missing_ref() {
+        let repo = TempDir::new("missing");
+        repo.write(".git/HEAD", "ref: refs/heads/main\n");
+        repo.write(".git/packed-refs", &format!("{} refs/heads/dev\n", ID));
+        assert!(head(&repo.0).is_err());
+    }
+
+    #[test]
+    fn worktree() {
+        let repo = TempDir::new("worktree");
+        repo.write("main/.git/HEAD", "ref: refs/heads/main\n");
+        repo.write("main/.git/refs/heads/main", &format!("{}\n", OTHER_ID));
+        repo.write("main/.git/packed-refs", &format!("{} refs/heads/feature\n", ID));
+        repo.write("main/.git/worktrees/wt/HEAD", "ref: refs/heads/feature\n");
+        repo.write("main/.git/worktrees/wt/commondir", "../..\n");
+        repo.write("wt/.git", "gitdir: ../main/.git/worktrees/wt\n");
+        let base_path = repo.0.join("wt");
+        assert_eq!(head(&base_path).unwrap(), ID);
+        let watched = watched_paths(&base_path);
+        assert_eq!(watched.len(), 4);
+        assert!(watched.iter().all(|path| path.exists()));
+    }
+
+    #[test]
+    fn detached_head() {
+        let repo = TempDir::new("detached");
+        repo.write(".git/HEAD", &format!("{}\n", ID));
+        assert_eq!(head(&repo.0).unwrap(), ID);
+        assert_eq!(abbrev(&head(&repo.0).unwrap()).unwrap(), "0123456");
+    }
+
+    #[test]
+    fn watched_paths_exist() {
+        let repo = TempDir::new("watched");
+        repo.write(".git/HEAD", &format!("{}\n", ID));
+        assert_eq!(watched_paths(&repo.0), [repo.0.join(".git/HEAD")]);
+        assert!(watched_paths(&repo.0.join("none")).is_empty());
+    }
+
+    #[test]
+    fn abbrev_id() {
+        assert_eq!(abbrev(ID).unwrap(), "0123456");
+        assert_eq!(abbrev(" ABCDEF0123\n").unwrap(), "abcdef0");
+        assert_eq!(abbrev("abcdef0").unwrap(), "abcdef0");
+        assert!(abbrev("abcdef").is_err());
+        assert!(abbrev("abcdefg123").is_err());
+        assert!(abbrev("").is_err());
+    }
+}
@@ -22,21 +22,55 @@ pub type TileColumns = Vec<Vec<Tile>>;
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct TileGrids {
-    pub left: Vec<Tile>,
-    pub middle: Vec<TileGridMiddle>,
+    pub sections: Vec<TileGridSection>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
-pub struct TileGridMiddle {
-    pub title: ByteString,
+pub struct TileGridSection {
+    #[serde(default)]
+    pub placement: TileGridPlacement,
+    /// required by every placement but `vertical`
+    pub title: Option<ByteString>,
+    /// the most tiles the stylesheet leaves room for, only for `trailing`,
+    /// the one placement whose room is not derived from the tile sizes by the validator
+    pub capacity: Option<usize>,
     pub content: Vec<Tile>,
 }
 
-// TODO Vec<CategoryTab>
+/// sections are laid out in this order, so they must also be written in it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TileGridPlacement {
+    /// a strip of its own, left of the middle block
+    Vertical,
+    /// a titled block inside the middle block
+    #[default]
+    Middle,
+    /// titled at the end of the middle block, with its tiles flowing after it
+    Trailing,
+}
+
+impl TileGridPlacement {
+    pub fn as_str(&self) -> &'static str {
+        use TileGridPlacement::*;
+        match self {
+            Vertical => "vertical",
+            Middle => "middle",
+            Trailing => "trailing",
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct Category {
-    pub tool: CategoryTab,
-    pub link: CategoryTab,
+    /// the groups of every tab are balanced across this many columns
+    #[serde(default = "default_category_columns")]
+    pub columns: usize,
+    pub tabs: Vec<CategoryTab>,
+}
+
+const fn default_category_columns() -> usize {
+    2
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -217,6 +251,14 @@ pub struct Tool {
     pub cross_top: Option<Vec<ByteString>>,
     pub keywords: Option<ByteString>,
     pub cross_notice: Option<Map<ByteString>>,
+    /// the reason, for discontinued tools
+    pub deprecated: Option<ByteString>,
+    /// the tool to use instead of a deprecated one
+    pub replaced_by: Option<ByteString>,
+    /// kept in the data and redirects, but left out of the index, categories, search and plain output
+    pub hidden: Option<bool>,
+    /// the former names, still accepted in the references and kept as anchors and redirect keys
+    pub aliases: Option<Vec<ByteString>>,
     #[serde(flatten)]
     pub links: ToolLinks,
 }
@@ -239,19 +281,121 @@ pub struct ToolLinks {
     pub websites: Option<Map<ToolLinkTitle>>,
     pub websites_tile: Option<Map<ToolLinkTitle>>,
     pub websites_tile_template: Option<TileTemplateInner>,
-    pub downloads: Option<Map<ByteString>>,
-    pub downloads_groups: Option<Map<Map<ByteString>>>,
+    pub downloads: Option<Map<ToolDownload>>,
+    pub downloads_groups: Option<Map<Map<ToolDownload>>>,
     pub mirror: Option<MirrorType>,
-    pub mirrors: Option<Map<ByteString>>,
+    pub mirrors: Option<Map<ToolMirror>>,
     pub columns: Option<bool>,
 }
 
+/// an entry in `downloads` or a group of `downloads_groups`, either just its title or with the details of the file
+#[derive(Clone, Debug)]
+pub struct ToolDownload {
+    title: ByteString,
+    info: Option<ToolDownloadInfo>,
+}
+
+impl ToolDownload {
+    pub fn title(&self) -> &ByteString {
+        &self.title
+    }
+
+    pub fn info(&self) -> Option<&ToolDownloadInfo> {
+        self.info.as_ref()
+    }
+}
+
+/// the details form of `ToolDownload`, checked strictly so that a misspelled field is an error
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ToolDownloadDetailed {
+    title: ByteString,
+    version: Option<ByteString>,
+    size: Option<u64>,
+    sha256: Option<ByteString>,
+    #[serde(default)]
+    platforms: Vec<ByteString>,
+    date: Option<ByteString>,
+}
+
+// not `untagged`, which would hide the errors of the details form (and their location) behind a generic one
+impl<'de> Deserialize<'de> for ToolDownload {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<ToolDownload, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = ToolDownload;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a title, or a map with a `title` and the details of the file")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, title: &str) -> Result<ToolDownload, E> {
+                Ok(ToolDownload { title: title.to_owned(), info: None })
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, map: A) -> Result<ToolDownload, A::Error> {
+                let ToolDownloadDetailed { title, version, size, sha256, platforms, date } =
+                    Deserialize::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
+                Ok(ToolDownload { title, info: Some(ToolDownloadInfo { version, size, sha256, platforms, date }) })
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToolDownloadInfo {
+    pub version: Option<ByteString>,
+    /// in bytes
+    pub size: Option<u64>,
+    pub sha256: Option<ByteString>,
+    /// architectures or systems, e.g. `x64`, `arm64`, `win7`
+    #[serde(default)]
+    pub platforms: Vec<ByteString>,
+    /// the release date, `YYYY-MM-DD`
+    pub date: Option<ByteString>,
+}
+
+/// an entry in `mirrors`, either just its title or with a type other than the `mirror` of the tool
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ToolMirror {
+    Title(ByteString),
+    Typed {
+        title: ByteString,
+        #[serde(rename = "type")]
+        mirror_type: MirrorType,
+    },
+}
+
+impl ToolMirror {
+    pub fn title(&self) -> &ByteString {
+        match self {
+            ToolMirror::Title(title) | ToolMirror::Typed { title, .. } => title,
+        }
+    }
+
+    pub fn mirror_type(&self) -> Option<MirrorType> {
+        match self {
+            ToolMirror::Title(_) => None,
+            ToolMirror::Typed { mirror_type, .. } => Some(*mirror_type),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct ToolLink {
     pub title: ToolLinkTitle,
     pub link_type: ToolLinkType,
     pub link: ByteString,
     pub icon: ToolLinkIcon,
+    /// the state of mirror links
+    pub mirror: Option<MirrorType>,
+    /// the details of download links given as objects
+    pub download: Option<ToolDownloadInfo>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -261,6 +405,15 @@ pub enum ToolLinkTitle {
     Text(ByteString),
 }
 
+pub const fn mirror_type_label(t: MirrorType) -> &'static str {
+    use MirrorType::*;
+    match t {
+        Active => "持续更新",
+        Locked => "固定版本",
+        Synced => "同步官方",
+    }
+}
+
 pub const fn tool_website_type(t: ToolLinkTitleType) -> &'static str {
     use ToolLinkTitleType::*;
     match t {
@@ -280,8 +433,8 @@ pub const fn tool_website_type(t: ToolLinkTitleType) -> &'static str {
 pub const fn tool_link_prefix(t: ToolLinkType) -> &'static str {
     use ToolLinkType::*;
     match t {
-        R2 => "//r.ldt.pc.wiki/r2/",
-        Mirror => "//r.ldt.pc.wiki/mirror/",
+        R2 => "{{REDIRECT}}/r2/",
+        Mirror => "{{REDIRECT}}/mirror/",
     }
 }
 
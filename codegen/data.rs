@@ -20,7 +20,34 @@ pub struct ToolCategoryItem {
     pub list: Vec<ByteString>,
 }
 
-pub type ToolAll = Map<ByteString>;
+pub type ToolSearch = Vec<ToolSearchRecord>;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ToolSearchRecord {
+    pub name: ByteString,
+    /// the index group listing the tool, if any
+    pub group: Option<ByteString>,
+    pub categories: Vec<ByteString>,
+    pub fields: Vec<ToolSearchField>,
+}
+
+/// the frontend ranks a record by the heaviest field matching, then by how a token of it matches:
+/// the text or pinyin by prefix, the initials by prefix, any of them by substring
+#[derive(Clone, Debug, Serialize)]
+pub struct ToolSearchField {
+    pub weight: u8,
+    pub tokens: Vec<ToolSearchKey>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ToolSearchKey {
+    pub text: ByteString,
+    /// only spelled for the title and keywords, and only if it differs from `text`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pinyin: Option<ByteString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initials: Option<ByteString>,
+}
 
 pub type ToolCross = Map<Map<ByteString>>;
 
@@ -28,7 +55,7 @@ pub type ToolCross = Map<Map<ByteString>>;
 pub struct ToolData {
     pub index: ToolIndex,
     pub category: ToolCategory,
-    pub all: ToolAll,
+    pub search: ToolSearch,
     pub cross: ToolCross,
 }
 
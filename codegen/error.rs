@@ -0,0 +1,172 @@
+use std::{fmt, io, path::{Path, PathBuf}};
+use crate::ByteString;
+
+#[derive(Debug)]
+pub enum ErrorKind {
+    Io(io::Error),
+    Yaml(serde_yaml::Error),
+    Json(serde_json::Error),
+    Esbuild(std::process::ExitStatus),
+    Invalid(ByteString),
+}
+
+#[derive(Debug)]
+pub struct Error {
+    pub file: Option<PathBuf>,
+    pub path: Option<ByteString>,
+    pub kind: ErrorKind,
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+impl Error {
+    pub fn new(kind: ErrorKind) -> Error {
+        Error { file: None, path: None, kind }
+    }
+
+    pub fn invalid<S: Into<ByteString>>(msg: S) -> Error {
+        Error::new(ErrorKind::Invalid(msg.into()))
+    }
+
+    /// sets the source file, keeping the innermost one if already set
+    pub fn at<P: AsRef<Path>>(mut self, file: P) -> Error {
+        if self.file.is_none() {
+            self.file = Some(file.as_ref().to_owned());
+        }
+        self
+    }
+
+    /// sets the logical path inside the source file, e.g. `[12].list[3].cross_notice`
+    pub fn path<S: Into<ByteString>>(mut self, path: S) -> Error {
+        if self.path.is_none() {
+            self.path = Some(path.into());
+        }
+        self
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::new(ErrorKind::Io(e))
+    }
+}
+
+impl From<serde_yaml::Error> for Error {
+    fn from(e: serde_yaml::Error) -> Error {
+        Error::new(ErrorKind::Yaml(e))
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Error {
+        Error::new(ErrorKind::Json(e))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = match &self.kind {
+            ErrorKind::Yaml(e) => e.location().map(|l| (l.line(), l.column())),
+            _ => None,
+        };
+        if let Some(file) = &self.file {
+            write!(f, "{}", file.display())?;
+            if let Some((line, column)) = location {
+                write!(f, ":{}:{}", line, column)?;
+            }
+            f.write_str(": ")?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "{}: ", path)?;
+        }
+        match &self.kind {
+            ErrorKind::Io(e) => write!(f, "{}", e),
+            ErrorKind::Yaml(e) => {
+                // the location is already printed in front
+                let msg = e.to_string();
+                let msg = match location {
+                    Some((line, column)) => msg.trim_end_matches(&format!(" at line {} column {}", line, column)).to_owned(),
+                    None => msg,
+                };
+                f.write_str(&msg)
+            },
+            ErrorKind::Json(e) => write!(f, "{}", e),
+            ErrorKind::Esbuild(status) => write!(f, "esbuild failed ({})", status),
+            ErrorKind::Invalid(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub trait ResultExt<T> {
+    fn at<P: AsRef<Path>>(self, file: P) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> ResultExt<T> for core::result::Result<T, E> {
+    fn at<P: AsRef<Path>>(self, file: P) -> Result<T> {
+        self.map_err(|e| e.into().at(file))
+    }
+}
+
+/// collects errors so that a single run reports as many of them as possible
+#[derive(Debug, Default)]
+pub struct Errors(Vec<Error>);
+
+impl Errors {
+    pub fn new() -> Errors {
+        Errors(Vec::new())
+    }
+
+    pub fn push(&mut self, e: Error) {
+        self.0.push(e);
+    }
+
+    pub fn extend(&mut self, other: Errors) {
+        self.0.extend(other.0);
+    }
+
+    pub fn collect<T>(&mut self, r: Result<T>) -> Option<T> {
+        match r {
+            Ok(v) => Some(v),
+            Err(e) => {
+                self.push(e);
+                None
+            },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, Error> {
+        self.0.iter()
+    }
+
+    /// returns the collected errors (if any) and leaves `self` empty
+    pub fn check(&mut self) -> core::result::Result<(), Errors> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(core::mem::take(self))
+        }
+    }
+}
+
+impl From<Error> for Errors {
+    fn from(e: Error) -> Errors {
+        Errors(vec![e])
+    }
+}
+
+impl fmt::Display for Errors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for e in &self.0 {
+            writeln!(f, "error: {}", e)?;
+        }
+        write!(f, "{} error(s) in total", self.0.len())
+    }
+}
+
+impl std::error::Error for Errors {}
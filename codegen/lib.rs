@@ -37,12 +37,12 @@ fn insert(input: &str, inserts: Inserts) -> String {
 
 struct GlobalReplacer<const N: usize> {
     replacer: AhoCorasick,
-    replaces: [&'static str; N],
+    replaces: [ByteString; N],
 }
 
 impl<const N: usize> GlobalReplacer<N> {
-    fn build(patterns: [&'static str; N], replaces: [&'static str; N]) -> GlobalReplacer<N> {
-        GlobalReplacer { replacer: AhoCorasick::new(patterns).unwrap(), replaces }
+    fn build(patterns: [&'static str; N], replaces: [&str; N]) -> GlobalReplacer<N> {
+        GlobalReplacer { replacer: AhoCorasick::new(patterns).unwrap(), replaces: replaces.map(ToOwned::to_owned) }
     }
 
     fn replace(&self, input: &str) -> String {
@@ -51,16 +51,32 @@ impl<const N: usize> GlobalReplacer<N> {
 }
 
 pub mod util;
+pub mod commit;
+pub mod error;
 pub mod config;
+pub mod site;
+pub mod validate;
+pub mod assets;
+pub mod redirects;
+pub mod manifest;
+pub mod sitemap;
+pub mod watch;
+pub mod serve;
+#[cfg(feature = "native-minify")]
+pub mod native;
 pub mod data;
+pub mod search;
+pub mod catalog;
 pub mod jsldr;
 pub mod codegen;
-use util::{IndexMapFirstInsert, VecMap};
+use util::IndexMapFirstInsert;
+use error::{Error, Errors, ErrorKind, ResultExt};
 use codegen::codegen;
 
-use std::{fs, path::{Path, PathBuf}, io::Write, sync::OnceLock};
+use std::{fs, path::{Path, PathBuf}, io::Write, sync::OnceLock, cell::RefCell};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Config {
     Prod,
     Dev,
@@ -74,14 +90,6 @@ impl Config {
             Dev => "Dev",
         }
     }
-
-    const fn assert(&self) -> &'static str {
-        use Config::*;
-        match self {
-            Prod => "//s0.ldt.pc.wiki",
-            Dev => "..",
-        }
-    }
 }
 
 impl core::str::FromStr for Config {
@@ -105,16 +113,16 @@ pub enum FileType {
 use FileType::*;
 
 impl FileType {
-    fn parse(name: &str) -> Self {
-        if name.ends_with(Html.as_src()) {
+    fn parse<P: AsRef<Path>>(path: P, name: &str) -> error::Result<Self> {
+        Ok(if name.ends_with(Html.as_src()) {
             Html
         } else if name.ends_with(Css.as_src()) {
             Css
         } else if name.ends_with(Script.as_src()) {
             Script
         } else {
-            unreachable!()
-        }
+            return Err(Error::invalid(cs!("unsupported file type: ", name)).at(path))
+        })
     }
 
     fn as_src(&self) -> &'static str {
@@ -141,10 +149,16 @@ impl FileType {
     }
 }
 
-const COPYRIGHT_L: &str = "
-  Copyright (c) 2021-2025 CarrotGeball and stackinspector. All rights reserved. MIT license.
-  Source: https://github.com/stackinspector/ldtstore-homepage
-  Commit (content): ";
+const COMMIT_L: &str = "  Commit (content): ";
+
+/// the loader script for `Render::Jsldr` dests, in `dynamic/code/`
+const JSLDR: &str = "jsldr.ts";
+
+/// the template for `Render::Jsldr` dests, in `static/` but not copied
+const JSLDR_INDEX: &str = "jsldr_index.html";
+
+/// after the content commit in the banners
+const DIRTY: &str = " (with uncommitted changes)";
 
 const COPYRIGHT_R: &str = concat!("
   Commit (codegen): ", env!("GIT_HASH"), "\n");
@@ -158,12 +172,39 @@ pub struct Args {
     /// source path (default .)
     #[argh(option, short = 's', default = "Default::default()")]
     base_path: PathBuf,
-    /// mode
+    /// environment in site.yml
     #[argh(option, short = 'c')]
-    config: Config,
-    /// esbuild binary path
+    config: String,
+    /// esbuild binary path (with the `native-minify` feature, esbuild is only used if this is given)
     #[argh(option)]
     esbuild_path: Option<PathBuf>,
+    /// asset root (containing `image/`) to check the generated references against
+    #[argh(option, long = "assets")]
+    assets_path: Option<PathBuf>,
+    /// content commit id, instead of reading it from `.git` (also settable with LDTSTORE_COMMIT)
+    #[argh(option)]
+    commit: Option<String>,
+    /// name code and boot files by a digest of their content instead of the commit
+    #[argh(switch)]
+    hash_names: bool,
+    /// remove the dest before building
+    #[argh(switch)]
+    clean: bool,
+    /// overwrite existing files in the dest
+    #[argh(switch)]
+    overwrite: bool,
+    /// build into a new dir beside the dest, and point the dest (a symlink) to it only if everything succeeded
+    #[argh(switch)]
+    atomic: bool,
+    #[argh(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(argh::FromArgs)]
+#[argh(subcommand)]
+pub enum Command {
+    Watch(watch::WatchArgs),
+    Serve(serve::ServeArgs),
 }
 
 static ARGS: OnceLock<Args> = OnceLock::new();
@@ -172,65 +213,112 @@ fn args<'a>() -> &'a Args {
     ARGS.get().unwrap()
 }
 
-fn read_commit<P: AsRef<Path>>(base_path: P) -> String {
-    let base_path = base_path.as_ref();
-    let head = load(base_path.join(".git/HEAD"));
-    let head = head.split('\n').next().unwrap();
-    let head = head.split("ref: ").nth(1).unwrap();
-    let commit = fs::read(base_path.join(".git").join(head)).unwrap();
-    String::from_utf8(commit[0..7].to_vec()).unwrap()
+static ENV: OnceLock<site::Environment> = OnceLock::new();
+
+fn env<'a>() -> &'a site::Environment {
+    ENV.get().unwrap()
 }
 
-fn load<P: AsRef<Path>>(path: P) -> String {
-    fs::read_to_string(path).unwrap()
+fn load_env(args: &Args) -> error::Result<site::Environment> {
+    let site_path = args.base_path.join(SITE);
+    let mut site: site::Site = load_yaml(&site_path)?;
+    let env = site.environments.shift_remove(&args.config).ok_or_else(|| {
+        let names: Vec<&str> = site.environments.keys().map(String::as_str).collect();
+        Error::invalid(cs!("unknown environment `", args.config, "`, expected one of ", names.join(", "))).at(&site_path)
+    })?;
+    env.check().map_err(|msg| Error::invalid(msg).at(&site_path).path(s!("environments.", args.config)))?;
+    Ok(env)
 }
 
-fn load_yaml<D: serde::de::DeserializeOwned, P: AsRef<Path>>(path: P) -> D {
-    serde_yaml::from_reader(fs::File::open(path).unwrap()).unwrap()
+/// the `dns-prefetch` links to the asset and redirect hosts, leaving out a relative asset base
+fn dns_prefetch(env: &site::Environment) -> ByteString {
+    let mut res = ByteString::new();
+    for base in [&env.assets, &env.redirect] {
+        if base.starts_with("//") {
+            res.push_str(&s!("<link rel=\"dns-prefetch\" href=\"", base, "\">\n"));
+        }
+    }
+    res
 }
 
-fn create<P: AsRef<Path>>(path: P) -> fs::File {
-    fs::OpenOptions::new().create_new(true).write(true).open(path).unwrap()
+/// the `<!--{{` and `}}-->` around the name of an inserted fragment
+const FRAGMENT_MARKER: (&str, &str) = ("<!--{{", "}}-->");
+
+/// the fragment markers in `html`, e.g. `<!--{{footer.html}}-->`
+fn fragment_markers(html: &str) -> impl Iterator<Item = &str> {
+    html.match_indices(FRAGMENT_MARKER.0).filter_map(|(start, _)| {
+        let end = html[start..].find(FRAGMENT_MARKER.1)?;
+        Some(&html[start..(start + end + FRAGMENT_MARKER.1.len())])
+    })
 }
 
-fn create_json<D: serde::Serialize, P: AsRef<Path>>(value: &D, dest: P) {
-    let mut file = create(dest);
-    serde_json::to_writer(&mut file, value).unwrap();
+/// the banner lines, indented as in the copyright comments
+fn banner(text: &str) -> String {
+    let mut banner = s!("\n");
+    for line in text.lines() {
+        banner.push_str("  ");
+        banner.push_str(line);
+        banner.push('\n');
+    }
+    banner
 }
 
-fn build_static_inserts(fragment_path: PathBuf) -> (Inserts, Map<String>) {
-    let mut inserts = Inserts::new();
-    let mut minifieds = Map::new();
-    for entry in fs::read_dir(fragment_path.clone()).unwrap() {
-        let entry = entry.unwrap();
-        if entry.metadata().unwrap().is_file() {
-            let file_name = entry.file_name();
-            let file_name = file_name.to_str().unwrap();
-            match FileType::parse(file_name) {
-                Html => {
-                    add_insert! {
-                        inserts:
-                        "<!--{{", file_name, "}}-->" => load(entry.path())
-                    }
-                },
-                Css => {
-                    minifieds.first_insert(s!(file_name), minify_css(entry.path()));
-                },
-                Script => {
-                    minifieds.first_insert(s!(file_name), compile_script(entry.path()));
+/// the environments picked with `-c`
+const SITE: &str = "site.yml";
 
-                },
-            }
+/// the ICP registration numbers
+const DOMAINS: &str = "domains.yml";
+
+/// the redirect table, checked against the generated links and exported for the redirect service
+const REDIRECTS: &str = "redirects.yml";
+
+/// the source dirs and files, checked for uncommitted changes and watched
+const CONTENT_PATHS: [&str; 7] = ["page", "fragment", "dynamic", "static", SITE, DOMAINS, REDIRECTS];
+
+fn load<P: AsRef<Path>>(path: P) -> error::Result<String> {
+    fs::read_to_string(&path).at(path)
+}
+
+fn load_yaml<D: serde::de::DeserializeOwned, P: AsRef<Path>>(path: P) -> error::Result<D> {
+    // serde_yaml already reports the logical path (e.g. `[12].list[3].cross_notice`) in its message
+    serde_yaml::from_reader(fs::File::open(&path).at(&path)?).at(path)
+}
+
+fn create<P: AsRef<Path>>(path: P, overwrite: bool) -> error::Result<fs::File> {
+    fs::OpenOptions::new().create_new(!overwrite).create(true).truncate(true).write(true).open(&path).at(path)
+}
+
+/// lists the files (or the subdirectories if `dir`) in `path` as `(file_name, full_path)`
+fn list_dir<P: AsRef<Path>>(path: P, dir: bool) -> error::Result<Vec<(String, PathBuf)>> {
+    let path = path.as_ref();
+    let mut res = Vec::new();
+    for entry in fs::read_dir(path).at(path)? {
+        let entry = entry.at(path)?;
+        let entry_path = entry.path();
+        let is_dir = entry.metadata().at(&entry_path)?.is_dir();
+        if is_dir == dir {
+            let file_name = entry.file_name().into_string()
+                .map_err(|_| Error::invalid("file name is not valid utf-8").at(&entry_path))?;
+            res.push((file_name, entry_path));
         }
     }
-    // add_insert! {
-    //     res:
-    //     "{{COMMIT}}" => commit
-    // }
-    (inserts, minifieds)
+    Ok(res)
+}
+
+enum Fragment {
+    Insert(String),
+    Minified(String),
+}
+
+fn build_fragment(file_name: &str, path: &Path, icpreg_map: &str) -> error::Result<Fragment> {
+    Ok(match FileType::parse(path, file_name)? {
+        Html => Fragment::Insert(load(path)?),
+        Css => Fragment::Minified(minify_css(path)?),
+        Script => Fragment::Minified(compile_script(path)?.replace(ICPREG_MAP, icpreg_map)),
+    })
 }
 
-fn call_esbuilld_cli<P: AsRef<Path>>(full_path: P, cmdargs: &'static [&'static str]) -> String {
+fn call_esbuilld_cli<P: AsRef<Path>>(full_path: P, cmdargs: &'static [&'static str]) -> error::Result<String> {
     use std::process::{Command, Stdio, Output};
     let mut command = if let Some(path) = &args().esbuild_path {
         Command::new(path)
@@ -243,22 +331,35 @@ fn call_esbuilld_cli<P: AsRef<Path>>(full_path: P, cmdargs: &'static [&'static s
         .stdin(Stdio::null())
         .stderr(Stdio::inherit())
         .stdout(Stdio::piped())
-        .output().unwrap();
-    assert!(status.success());
-    String::from_utf8(stdout).unwrap()
+        .output()
+        .map_err(|e| Error::invalid(cs!("failed to run esbuild: ", e.to_string())).at(&full_path))?;
+    if !status.success() {
+        return Err(Error::new(ErrorKind::Esbuild(status)).at(full_path));
+    }
+    String::from_utf8(stdout).map_err(|_| Error::invalid("esbuild output is not valid utf-8").at(full_path))
 }
 
-fn minify_css<P: AsRef<Path>>(full_path: P) -> String {
-    match args().config {
-        Config::Prod => call_esbuilld_cli(full_path, &[
-            "--minify",
-        ]),
+fn minify_css<P: AsRef<Path>>(full_path: P) -> error::Result<String> {
+    match env().mode {
+        Config::Prod => {
+            #[cfg(feature = "native-minify")]
+            if args().esbuild_path.is_none() {
+                return native::minify_css(full_path.as_ref());
+            }
+            call_esbuilld_cli(full_path, &[
+                "--minify",
+            ])
+        },
         Config::Dev => load(full_path),
     }
 }
 
-fn compile_script<P: AsRef<Path>>(full_path: P) -> String {
-    match args().config {
+fn compile_script<P: AsRef<Path>>(full_path: P) -> error::Result<String> {
+    #[cfg(feature = "native-minify")]
+    if args().esbuild_path.is_none() {
+        return native::compile_script(full_path.as_ref(), matches!(env().mode, Config::Prod));
+    }
+    match env().mode {
         Config::Prod => call_esbuilld_cli(full_path, &[
             "--minify-whitespace",
             "--minify-syntax",
@@ -274,6 +375,9 @@ fn compile_script<P: AsRef<Path>>(full_path: P) -> String {
     }
 }
 
+/// in bytes
+const HASH_NAME_LEN: usize = 5;
+
 struct IntegrityBuilder {
     hasher: sha2::Sha512,
 }
@@ -288,310 +392,1027 @@ impl IntegrityBuilder {
         self.hasher.update(data);
     }
 
-    fn output(self) -> String {
+    /// returns the SRI integrity string and the truncated hex digest used by `--hash-names`
+    fn output(self) -> (String, String) {
         use sha2::Digest;
         let hash = self.hasher.finalize();
         let mut integrity = "sha512-".to_owned();
         data_encoding::BASE64.encode_append(&hash, &mut integrity);
-        integrity
+        (integrity, data_encoding::HEXLOWER.encode(&hash[..HASH_NAME_LEN]))
     }
 }
 
 // fn complie_file(file_name: &str) -> (FileType, String) {}
 
+/// the dest name of a file in `static/`, or `None` if it is not for this mode,
+/// e.g. `robots.dev.txt` is `robots.txt` in dev builds and skipped in prod builds
+fn static_dest_name(file_name: &str, config: Config) -> Option<ByteString> {
+    let mut parts = file_name.rsplitn(3, '.');
+    let (ext, mode, stem) = (parts.next(), parts.next().and_then(|mode| mode.parse::<Config>().ok()), parts.next());
+    match (stem, mode, ext) {
+        (Some(stem), Some(mode), Some(ext)) => (mode == config).then(|| s!(stem, ".", ext)),
+        _ => Some(s!(file_name)),
+    }
+}
+
 fn firstname(file_name: &str, ty: FileType) -> &str {
     let b = file_name.as_bytes();
     let l = b.len() - ty.as_src().len() - 1;
     std::str::from_utf8(&b[..l]).unwrap()
 }
 
-pub fn build(args: Args) {
-    let Args { dest_path, base_path, config, .. } = ARGS.get_or_init(|| args);
+#[derive(Clone, Debug, serde::Deserialize)]
+struct PageConfig {
+    lang: Option<ByteString>,
+    #[serde(default)]
+    css: Vec<ByteString>,
+    #[serde(default)]
+    js: Vec<ByteString>,
+    #[serde(default)]
+    minified_css: Vec<ByteString>,
+    #[serde(default)]
+    minified_js: Vec<ByteString>,
+    dest: Vec<Dest>,
+}
 
-    let commit: String = read_commit(base_path);
-    let (mut inserts, minifieds) = build_static_inserts(base_path.join("fragment"));
-    let mut includes = Map::new();
-    codegen(&mut inserts, &mut includes, base_path.join("page"));
-    let global_replacer = GlobalReplacer::build(
-        ["<a n ", "{{ASSERT}}"],
-        [r#"<a target="_blank" "#, config.assert()],
-    );
-
-    let dynamic_base = base_path.join("dynamic");
-    let dynamic_code_base = dynamic_base.join("code");
-    let dynamic_page_base = dynamic_base.join("page");
-    let dest_code_base = dest_path.join("code");
-    let dest_page_boot_base = dest_path.join("page-boot");
-
-    fs::create_dir_all(&dest_code_base).unwrap();
-    fs::create_dir_all(&dest_page_boot_base).unwrap();
-
-    let replace_html = |path: PathBuf| {
-        global_replacer.replace(&insert(&load(path), inserts.clone()))
+#[derive(Clone, Debug, serde::Deserialize)]
+struct Dest {
+    dir: ByteString,
+    name: ByteString,
+    /// a domain in `domains.yml` for the static ICP registration, or `none`
+    reg: ByteString,
+    #[serde(default)]
+    render: Render,
+}
+
+/// what is written to a dest, list the same dir twice (with different names) to have both
+#[derive(Clone, Copy, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Render {
+    /// the fully rendered page
+    #[default]
+    Full,
+    /// `static/jsldr_index.html`, loading the boot JSON with `jsldr.ts` at runtime
+    /// (`reg` is not applied to the boot JSON, which is shared by every dest)
+    Jsldr,
+}
+
+/// `reg` of the dests without a static ICP registration
+const NO_REG: &str = "none";
+
+/// replaced with the `domains.yml` map in the compiled scripts, see `fragment/icpreg.ts`
+const ICPREG_MAP: &str = "__ICPREG_MAP__";
+
+fn replace_icpreg(icpreg: Option<&ByteString>, body: &ByteString) -> ByteString {
+    let replace = if let Some(icpreg) = icpreg {
+        cs!(
+            "<a target=\"_blank\" class=\"link hidden\" href=\"//beian.miit.gov.cn/\"><span>",
+            icpreg,
+            "</span></a>\n"
+        )
+    } else {
+        String::new()
     };
+    body.replace("<!--{{icpreg-static}}-->", &replace)
+}
 
-    let mut code_info = Map::new();
-
-    for entry in fs::read_dir(dynamic_code_base).unwrap() {
-        let entry = entry.unwrap();
-        if entry.metadata().unwrap().is_file() {
-            let path = entry.path();
-            let file_name = entry.file_name();
-            let file_name = file_name.to_str().unwrap();
-            let ty = FileType::parse(file_name);
-            let content = match ty {
-                Css => minify_css(path),
-                Script => compile_script(path),
-                Html => unreachable!(),
+fn make_includes(data: Option<&data::GlobalData>) -> Map<JsonValue> {
+    let mut includes = Map::new();
+    if let Some(data) = data {
+        includes.first_insert(s!("__DATA__"), serde_json::to_value(data).unwrap());
+    }
+    includes
+}
+
+fn lookup<T: Clone>(map: &Map<T>, files: Vec<ByteString>, config_path: &Path) -> error::Result<Vec<T>> {
+    files.into_iter().map(|file| {
+        map.get(file.as_str()).cloned().ok_or_else(|| Error::invalid(cs!("unknown file: ", file)).at(config_path))
+    }).collect()
+}
+
+/// a source that pages depend on, tracked for incremental rebuilds
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Source {
+    /// the `page/` data, i.e. everything produced by `codegen`
+    PageData,
+    /// a file in `fragment/`
+    Fragment(ByteString),
+    /// a file in `dynamic/code/`
+    Code(ByteString),
+    /// a file in `static/`
+    Static(ByteString),
+}
+
+struct Builder<'a> {
+    base_path: &'a Path,
+    commit: String,
+    /// the content tree has uncommitted changes
+    dirty: bool,
+    /// the `lastmod` in `sitemap.xml`
+    commit_date: Option<String>,
+    dest_path: &'a Path,
+    dest_code_base: PathBuf,
+    dest_page_boot_base: PathBuf,
+    overwrite: bool,
+    banner: String,
+    global_replacer: GlobalReplacer<5>,
+    domains: site::Domains,
+    /// `domains` as JSON
+    icpreg_map: String,
+    fragments: Map<String>,
+    codegen_inserts: Inserts,
+    minifieds: Map<String>,
+    includes: Map<data::GlobalData>,
+    /// the ids in each page, listed in `sitemap.xml` after its dests
+    anchors: Map<Vec<ByteString>>,
+    /// written to `tools.json`
+    catalog: Vec<catalog::Tool>,
+    code_info: Map<jsldr::Resource>,
+    /// sources of every successfully built page
+    page_sources: Map<Vec<Source>>,
+    /// dests of every successfully built page
+    page_dests: RefCell<Map<Vec<Dest>>>,
+    asset_refs: RefCell<assets::AssetRefs>,
+    /// `redirects.yml`, if present
+    redirects: Option<redirects::Redirects>,
+    redirect_refs: RefCell<redirects::RedirectRefs>,
+    emitted: RefCell<manifest::Files>,
+}
+
+impl<'a> Builder<'a> {
+    /// loads and compiles every source, without writing anything to the dest
+    fn new(args: &'a Args, dest_path: &'a Path, overwrite: bool) -> Result<Builder<'a>, Errors> {
+        let Args { base_path, .. } = args;
+        let env = env();
+        let mut errors = Errors::new();
+
+        let commit = errors.collect(
+            commit::resolve(base_path, args.commit.as_deref(), Some(&CONTENT_PATHS)).map_err(Error::invalid)
+        );
+        let domains: site::Domains = errors.collect(load_yaml(base_path.join(DOMAINS))).unwrap_or_default();
+        let icpreg_map = serde_json::to_string(&domains).unwrap();
+        let redirects_path = base_path.join(REDIRECTS);
+        let redirects: Option<redirects::Redirects> = if redirects_path.is_file() {
+            errors.collect(load_yaml(&redirects_path)).inspect(|redirects| {
+                errors.extend(redirects::validate(redirects, &redirects_path));
+            })
+        } else {
+            None
+        };
+        let mut fragments = Map::new();
+        let mut minifieds = Map::new();
+        for (file_name, path) in errors.collect(list_dir(base_path.join("fragment"), false)).unwrap_or_default() {
+            match errors.collect(build_fragment(&file_name, &path, &icpreg_map)) {
+                Some(Fragment::Insert(content)) => fragments.first_insert(file_name, content),
+                Some(Fragment::Minified(content)) => minifieds.first_insert(file_name, content),
+                None => {},
+            }
+        }
+        // add_insert! {
+        //     res:
+        //     "{{COMMIT}}" => commit
+        // }
+        let mut codegen_inserts = Inserts::new();
+        let mut includes = Map::new();
+        let mut anchors = Map::new();
+        let mut catalog = Vec::new();
+        if let Err(codegen_errors) = codegen(&mut codegen_inserts, &mut includes, &mut anchors, &mut catalog, base_path.join("page")) {
+            errors.extend(codegen_errors);
+        }
+        errors.check()?;
+
+        let commit = commit.unwrap();
+        Ok(Builder {
+            base_path,
+            dirty: commit.dirty,
+            commit_date: commit::date(base_path, &commit.id),
+            commit: commit.id,
+            dest_path,
+            dest_code_base: dest_path.join("code"),
+            dest_page_boot_base: dest_path.join("page-boot"),
+            overwrite,
+            banner: banner(&env.banner),
+            global_replacer: GlobalReplacer::build(
+                ["<a n ", "{{ASSERT}}", "{{REDIRECT}}", "{{SUBDOMAIN}}", "{{DNS_PREFETCH}}"],
+                [r#"<a target="_blank" "#, &env.assets, &env.redirect, &env.subdomain_suffix, &dns_prefetch(env)],
+            ),
+            domains,
+            icpreg_map,
+            fragments,
+            codegen_inserts,
+            minifieds,
+            includes,
+            anchors,
+            catalog,
+            code_info: Map::new(),
+            page_sources: Map::new(),
+            page_dests: RefCell::new(Map::new()),
+            asset_refs: RefCell::new(assets::AssetRefs::new()),
+            redirects,
+            redirect_refs: RefCell::new(redirects::RedirectRefs::new()),
+            emitted: RefCell::new(manifest::Files::new()),
+        })
+    }
+
+    fn inserts(&self) -> Inserts {
+        let mut inserts = Inserts::new();
+        for (file_name, content) in &self.fragments {
+            add_insert! {
+                inserts:
+                "<!--{{", file_name, "}}-->" => content
+            }
+        }
+        inserts.extend(self.codegen_inserts.iter().cloned());
+        inserts
+    }
+
+    fn replace_html(&self, html: &str, source: &str) -> String {
+        let html = insert(html, self.inserts());
+        self.asset_refs.borrow_mut().scan(&html, source);
+        self.redirect_refs.borrow_mut().scan(&html, source);
+        self.global_replacer.replace(&html)
+    }
+
+    /// the part of an output file name identifying its version
+    fn name_suffix<'s>(&'s self, digest: &'s str) -> &'s str {
+        if args().hash_names { digest } else { &self.commit }
+    }
+
+    fn build_code(&self, file_name: &str, path: &Path) -> error::Result<jsldr::Resource> {
+        let Builder { commit, dirty, banner, dest_code_base, global_replacer, .. } = self;
+        let env = env();
+        let ty = FileType::parse(path, file_name)?;
+        let content = match ty {
+            Css => minify_css(path)?,
+            Script => compile_script(path)?,
+            Html => return Err(Error::invalid("html files are not allowed here").at(path)),
+        };
+        self.asset_refs.borrow_mut().scan(&content, file_name);
+        self.redirect_refs.borrow_mut().scan(&content, file_name);
+        let content = global_replacer.replace(&content).replace(ICPREG_MAP, &self.icpreg_map);
+
+        let (comment_l, comment_r) = ty.comment();
+        let mut output = String::new();
+        let mut integrity = IntegrityBuilder::new();
+        macro_rules! w {
+            ($s:expr) => {
+                output.push_str($s);
+                integrity.update($s.as_bytes());
             };
-            let content = global_replacer.replace(&content);
-            let dest_name = cs!(firstname(file_name, ty), "-", commit, ".", ty.as_dest());
-            let dest = dest_code_base.join(&dest_name);
+        }
+        w!(comment_l);
+        w!(banner);
+        // the commits would change the digest on every commit
+        if !args().hash_names {
+            w!(COMMIT_L);
+            w!(commit);
+            if *dirty {
+                w!(DIRTY);
+            }
+            w!(COPYRIGHT_R);
+        }
+        w!("  ");
+        w!(env.mode.name());
+        w!(" build\n");
+        w!(comment_r);
+        w!("\n\n");
+        w!(&content);
+
+        let (integrity, digest) = integrity.output();
+        let dest_name = cs!(firstname(file_name, ty), "-", self.name_suffix(&digest), ".", ty.as_dest());
+        let dest = dest_code_base.join(&dest_name);
+        create(&dest, self.overwrite)?.write_all(output.as_bytes()).at(&dest)?;
+        let source = s!("dynamic/code/", file_name);
+        let mut emitted = self.emitted.borrow_mut();
+        emitted.forget(&source);
+        emitted.record(manifest::File {
+            source,
+            dest: s!("code/", dest_name),
+            size: output.len(),
+            integrity: integrity.clone(),
+        });
+
+        Ok(jsldr::Resource {
+            path: cs!(env.assets, "/code/", dest_name),
+            integrity: Some(integrity),
+        })
+    }
 
-            let (comment_l, comment_r) = ty.comment();
-            let mut file = create(dest);
+    fn build_page(&self, page_name: &str, path: &Path) -> error::Result<Vec<Source>> {
+        let Builder { commit, dirty, banner, dest_path, dest_page_boot_base, code_info, minifieds, includes, .. } = self;
+        let env = env();
+        let config_path = path.join("config.yml");
+        let lconfig: PageConfig = load_yaml(&config_path)?;
+        let icpregs = lconfig.dest.iter().map(|Dest { reg, .. }| {
+            if reg == NO_REG {
+                return Ok(None);
+            }
+            self.domains.get(reg).map(Some).ok_or_else(|| {
+                Error::invalid(cs!("unknown domain `", reg, "` in `reg`, expected `none` or a domain in domains.yml")).at(&config_path)
+            })
+        }).collect::<error::Result<Vec<_>>>()?;
+        let head = load(path.join("head.html"))?;
+        let body = load(path.join("body.html"))?;
+
+        let mut sources = Vec::new();
+        let uses = |pattern: &str| head.contains(pattern) || body.contains(pattern);
+        // every marker, as the fragment may not be there yet but should be picked up once it is
+        for marker in fragment_markers(&head).chain(fragment_markers(&body)) {
+            let source = Source::Fragment(s!(&marker[FRAGMENT_MARKER.0.len()..(marker.len() - FRAGMENT_MARKER.1.len())]));
+            if !self.codegen_inserts.iter().any(|(pattern, _)| pattern == marker) && !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+        if self.codegen_inserts.iter().any(|(pattern, _)| uses(pattern)) || includes.contains_key(page_name) {
+            sources.push(Source::PageData);
+        }
+        sources.extend(lconfig.css.iter().chain(&lconfig.js).cloned().map(Source::Code));
+        let jsldr = lconfig.dest.iter().any(|dest| matches!(dest.render, Render::Jsldr));
+        if jsldr {
+            sources.push(Source::Code(s!(JSLDR)));
+            sources.push(Source::Static(s!(JSLDR_INDEX)));
+        }
+        sources.extend(lconfig.minified_css.iter().chain(&lconfig.minified_js).cloned().map(Source::Fragment));
+
+        let head = self.replace_html(&head, page_name);
+        let body = self.replace_html(&body, page_name);
+        // TODO warn when head or body include <link> <style> <script>
+        // allow control-used <style>?
+        let boot = jsldr::Boot {
+            lang: lconfig.lang,
+            css: lookup(code_info, lconfig.css, &config_path)?,
+            minified_css: lookup(minifieds, lconfig.minified_css, &config_path)?,
+            minified_js: lookup(minifieds, lconfig.minified_js, &config_path)?,
+            js: lookup(code_info, lconfig.js, &config_path)?,
+            includes: make_includes(includes.get(page_name)),
+            head,
+            body,
+        };
+        let boot_json = serde_json::to_vec(&boot).at(&config_path)?;
+        let mut boot_integrity = IntegrityBuilder::new();
+        boot_integrity.update(&boot_json);
+        let (boot_integrity, boot_digest) = boot_integrity.output();
+        let boot_name = cs!(page_name, "-", self.name_suffix(&boot_digest), ".boot.json");
+        let boot_dest = dest_page_boot_base.join(&boot_name);
+        create(&boot_dest, self.overwrite)?.write_all(&boot_json).at(&boot_dest)?;
+        let source = s!("dynamic/page/", page_name);
+        self.emitted.borrow_mut().forget(&source);
+        self.emitted.borrow_mut().record(manifest::File {
+            source: source.clone(),
+            dest: s!("page-boot/", boot_name),
+            size: boot_json.len(),
+            integrity: boot_integrity,
+        });
+
+        let _ = self.page_dests.borrow_mut().insert(s!(page_name), lconfig.dest.clone());
+
+        let jsldr_index = if jsldr {
+            let template_path = self.base_path.join("static").join(JSLDR_INDEX);
+            let template = load(&template_path)?;
+            let jsldr = lookup(code_info, vec![s!(JSLDR)], &config_path)?.remove(0);
+            let boot_path = cs!(env.assets, "/page-boot/", boot_name);
+            let replacer = AhoCorasick::new(["{{BOOT_JSON_PATH}}", "{{JSLDR_PATH}}", "{{JSLDR_INTEGRITY}}"]).unwrap();
+            Some(replacer.replace_all(&template, &[boot_path, jsldr.path, jsldr.integrity.unwrap_or_default()]))
+        } else {
+            None
+        };
+
+        for (Dest { dir, name, render, .. }, icpreg) in lconfig.dest.into_iter().zip(icpregs) {
+            fs::create_dir_all(dest_path.join(&dir)).at(dest_path.join(&dir))?;
+            let dest_name = s!(dir, "/", name);
+            let dest = dest_path.join(dir).join(name);
+
+            let (comment_l, comment_r) = FileType::Html.comment();
+            let mut file = create(&dest, self.overwrite)?;
             let mut integrity = IntegrityBuilder::new();
+            let mut size = 0;
             macro_rules! w {
-                ($s:expr) => {
-                    file.write_all($s.as_bytes()).unwrap();
-                    integrity.update($s.as_bytes());
-                };
+                ($s:expr) => {{
+                    let s: &str = &$s;
+                    file.write_all(s.as_bytes()).at(&dest)?;
+                    integrity.update(s.as_bytes());
+                    size += s.len();
+                }};
             }
             w!(comment_l);
-            w!(COPYRIGHT_L);
+            w!(banner);
+            w!(COMMIT_L);
             w!(commit);
+            if *dirty {
+                w!(DIRTY);
+            }
             w!(COPYRIGHT_R);
             w!("  ");
-            w!(config.name());
+            w!(env.mode.name());
             w!(" build\n");
             w!(comment_r);
             w!("\n\n");
-            w!(content);
-
-            code_info.first_insert(s!(file_name), jsldr::Resource {
-                path: cs!(config.assert(), "/code/", dest_name),
-                integrity: Some(integrity.output()),
-            });
-        }
-    }
 
-    fn make_includes(data: Option<&data::GlobalData>) -> Map<JsonValue> {
-        let mut includes = Map::new();
-        if let Some(data) = data {
-            includes.first_insert(s!("__DATA__"), serde_json::to_value(data).unwrap());
-        }
-        includes
-    }
-
-    #[derive(Clone, Debug, serde::Deserialize)]
-    struct PageConfig {
-        lang: Option<ByteString>,
-        #[serde(default)]
-        css: Vec<ByteString>,
-        #[serde(default)]
-        js: Vec<ByteString>,
-        #[serde(default)]
-        minified_css: Vec<ByteString>,
-        #[serde(default)]
-        minified_js: Vec<ByteString>,
-        dest: Vec<Dest>,
-    }
-
-    #[derive(Clone, Debug, serde::Deserialize)]
-    struct Dest {
-        dir: ByteString,
-        name: ByteString,
-        reg: DomainReg,
-    }
-
-    #[derive(Clone, Debug, serde::Deserialize)]
-    enum DomainReg {
-        #[serde(rename = "none")]
-        None,
-        #[serde(rename = "pc.wiki")]
-        PC_WIKI,
-        #[serde(rename = "ldtstore.com.cn")]
-        LDTSTORE_COM_CN,
-    }
-
-    impl DomainReg {
-        fn icpreg(&self) -> Option<&'static str> {
-            match self {
-                DomainReg::None => None,
-                DomainReg::PC_WIKI => Some("鲁ICP备2023022036号"),
-                DomainReg::LDTSTORE_COM_CN => Some("鲁ICP备2021014114号"),
+            if let (Render::Jsldr, Some(jsldr_index)) = (render, &jsldr_index) {
+                w!(jsldr_index);
+                self.emitted.borrow_mut().record(manifest::File {
+                    source: source.clone(),
+                    dest: dest_name,
+                    size,
+                    integrity: integrity.output().0,
+                });
+                continue;
             }
-        }
 
-        fn replace_body(&self, body: &ByteString) -> ByteString {
-            let replace = if let Some(icpreg) = self.icpreg() {
-                cs!(
-                    "<a target=\"_blank\" class=\"link hidden\" href=\"//beian.miit.gov.cn/\"><span>",
-                    icpreg,
-                    "</span></a>\n"
-                )
+            w!("<!DOCTYPE html>\n");
+
+            if let Some(ref lang) = boot.lang {
+                w!("<html lang=\"");
+                w!(lang);
+                w!("\">\n");
             } else {
-                String::new()
-            };
-            body.replace("<!--{{icpreg-static}}-->", &replace)
-        }
-    }
-
-    for entry in fs::read_dir(&dynamic_page_base).unwrap() {
-        let entry = entry.unwrap();
-        if entry.metadata().unwrap().is_dir() {
-            let path = entry.path();
-            let page_name = entry.file_name();
-            let page_name = page_name.to_str().unwrap();
-            let lconfig: PageConfig = load_yaml(path.join("config.yml"));
-            let head = replace_html(path.join("head.html"));
-            let body = replace_html(path.join("body.html"));
-            // TODO warn when head or body include <link> <style> <script>
-            // allow control-used <style>?
-            let boot = jsldr::Boot {
-                lang: lconfig.lang,
-                css: lconfig.css.map_to(|file| code_info.get(file.as_str()).unwrap().clone()),
-                minified_css: lconfig.minified_css.map_to(|file| minifieds.get(file.as_str()).unwrap().clone()),
-                minified_js: lconfig.minified_js.map_to(|file| minifieds.get(file.as_str()).unwrap().clone()),
-                js: lconfig.js.map_to(|file| code_info.get(file.as_str()).unwrap().clone()),
-                includes: make_includes(includes.get(page_name)),
-                head,
-                body,
-            };
-            create_json(&boot, dest_page_boot_base.join(cs!(page_name, "-", commit, ".boot.json")));
-
-            for Dest { dir, name, reg } in lconfig.dest {
-                fs::create_dir_all(dest_path.join(&dir)).unwrap();
-                let dest = dest_path.join(dir).join(name);
-
-                let (comment_l, comment_r) = FileType::Html.comment();
-                let mut file = create(dest);
-                macro_rules! w {
-                    ($s:expr) => {
-                        file.write_all($s.as_bytes()).unwrap();
-                    };
+                w!("<html>\n");
+            }
+            w!("<head>\n");
+            w!(boot.head);
+            for css_content in boot.minified_css.iter() {
+                // TODO one tag?
+                w!("<style>");
+                w!(css_content);
+                w!("</style>\n");
+            }
+            for jsldr::Resource { path, integrity } in boot.css.iter() {
+                w!("<link rel=\"stylesheet\" href=\"");
+                w!(path);
+                if let Some(integrity) = integrity {
+                    w!("\" integrity=\"");
+                    w!(integrity);
                 }
-                w!(comment_l);
-                w!(COPYRIGHT_L);
-                w!(commit);
-                w!(COPYRIGHT_R);
-                w!("  ");
-                w!(config.name());
-                w!(" build\n");
-                w!(comment_r);
-                w!("\n\n<!DOCTYPE html>\n");
-
-                if let Some(ref lang) = boot.lang {
-                    w!("<html lang=\"");
-                    w!(lang);
-                    w!("\">\n");
-                } else {
-                    w!("<html>\n");
+                w!("\" crossorigin=\"anonymous\">\n");
+            }
+            w!("</head>\n<body>\n");
+            w!(replace_icpreg(icpreg, &boot.body));
+            for (key, data) in boot.includes.iter() {
+                // TODO one tag?
+                w!("<script>window.");
+                w!(key);
+                w!("=");
+                w!(serde_json::to_string(data).at(&dest)?);
+                w!("</script>\n");
+            }
+            for js_content in boot.minified_js.iter() {
+                w!("<script>");
+                w!(js_content);
+                w!("</script>\n");
+            }
+            for jsldr::Resource { path, integrity } in boot.js.iter() {
+                w!("<script src=\"");
+                w!(path);
+                if let Some(integrity) = integrity {
+                    w!("\" integrity=\"");
+                    w!(integrity);
                 }
-                w!("<head>\n");
-                w!(boot.head);
-                for css_content in boot.minified_css.iter() {
-                    // TODO one tag?
-                    w!("<style>");
-                    w!(css_content);
-                    w!("</style>\n");
+                w!("\" crossorigin=\"anonymous\"></script>\n");
+            }
+            w!("</body>\n</html>");
+            self.emitted.borrow_mut().record(manifest::File {
+                source: source.clone(),
+                dest: dest_name,
+                size,
+                integrity: integrity.output().0,
+            });
+
+            /*
+
+            use foundations::vec_ext;
+            use lighthtml::{*, prelude::*};
+            macro_rules! text {
+                ($s:expr) => {
+                    vec![Text($s)]
+                };
+            }
+            w!(&render_node(Element(E_HTML, vec_ext![
+                @if let (Some(ref lang) = boot.lang) {
+                    (A_LANG, s!(lang))
                 }
-                for jsldr::Resource { path, integrity } in boot.css.iter() {
-                    w!("<link rel=\"stylesheet\" href=\"");
-                    w!(path);
-                    if let Some(integrity) = integrity {
-                        w!("\" integrity=\"");
-                        w!(integrity);
+            ], vec![
+                Element(E_HEAD, attr!{}, vec_ext![
+                    Html(s!(&boot.head)),
+                    @for (css_content in boot.minified_css.iter()) {
+                        // TODO one tag?
+                        Element(E_STYLE, attr!{}, text!(s!(css_content)))
+                    },
+                    @for (jsldr::Resource { path, integrity } in boot.css.iter()) {
+                        Element(E_LINK, vec_ext![
+                            (A_REL, s!("stylesheet")),
+                            (A_HREF, s!(path)),
+                            @if let (Some(integrity) = integrity) {
+                                (A_INTEGRITY, s!(integrity))
+                            },
+                            (A_CROSSORIGIN, s!("anonymous")),
+                        ], vec![])
                     }
-                    w!("\" crossorigin=\"anonymous\">\n");
-                }
-                w!("</head>\n<body>\n");
-                w!(reg.replace_body(&boot.body));
-                for (key, data) in boot.includes.iter() {
-                    // TODO one tag?
-                    w!("<script>window.");
-                    w!(key);
-                    w!("=");
-                    serde_json::to_writer(&mut file, data).unwrap();
-                    w!("</script>\n");
-                }
-                for js_content in boot.minified_js.iter() {
-                    w!("<script>");
-                    w!(js_content);
-                    w!("</script>\n");
-                }
-                for jsldr::Resource { path, integrity } in boot.js.iter() {
-                    w!("<script src=\"");
-                    w!(path);
-                    if let Some(integrity) = integrity {
-                        w!("\" integrity=\"");
-                        w!(integrity);
+                ]),
+                Element(E_BODY, attr!{}, vec_ext![
+                    Html(replace_icpreg(icpreg, &boot.body)),
+                    @for ((key, data) in boot.includes.iter()) {
+                        // TODO one tag?
+                        Element(E_SCRIPT, attr!{}, text!(s!(
+                            "window.",
+                            key,
+                            "=",
+                            serde_json::to_string(data).unwrap()
+                        )))
+                    },
+                    @for (js_content in boot.minified_js.iter()) {
+                        Element(E_SCRIPT, attr!{}, text!(s!(js_content)))
+                    },
+                    @for (jsldr::Resource { path, integrity } in boot.js.iter()) {
+                        Element(E_SCRIPT, vec_ext![
+                            (A_HREF, s!(path)),
+                            @if let (Some(integrity) = integrity) {
+                                (A_INTEGRITY, s!(integrity))
+                            },
+                            (A_CROSSORIGIN, s!("anonymous")),
+                        ], vec![])
                     }
-                    w!("\" crossorigin=\"anonymous\"></script>\n");
-                }
-                w!("</body>\n</html>");
+                ]),
+            ])));
+        */
+
+        }
+
+        Ok(sources)
+    }
+
+    /// copies a file in `static/` to the root of the dest, through `replace_html` if it has placeholders
+    fn build_static(&self, file_name: &str, path: &Path) -> error::Result<()> {
+        let source = s!("static/", file_name);
+        self.emitted.borrow_mut().forget(&source);
+        let Some(dest_name) = static_dest_name(file_name, env().mode) else {
+            return Ok(());
+        };
+        let content = fs::read(path).at(path)?;
+        let content = match std::str::from_utf8(&content) {
+            Ok(text) if text.contains("{{") => self.replace_html(text, &source).into_bytes(),
+            _ => content,
+        };
+        let dest = self.dest_path.join(&dest_name);
+        create(&dest, self.overwrite)?.write_all(&content).at(&dest)?;
+        let mut integrity = IntegrityBuilder::new();
+        integrity.update(&content);
+        self.emitted.borrow_mut().record(manifest::File {
+            source,
+            dest: dest_name,
+            size: content.len(),
+            integrity: integrity.output().0,
+        });
+        Ok(())
+    }
+
+    fn build_statics(&self, errors: &mut Errors) {
+        let static_base = self.base_path.join("static");
+        if !static_base.is_dir() {
+            return;
+        }
+        for (file_name, path) in errors.collect(list_dir(&static_base, false)).unwrap_or_default() {
+            if file_name != JSLDR_INDEX {
+                let _ = errors.collect(self.build_static(&file_name, &path));
+            }
+        }
+    }
 
-                /*
+    /// exports `redirects.yml` to `redirects/` in the dest, for the redirect service
+    fn write_redirects(&self) -> error::Result<()> {
+        let Some(redirects) = &self.redirects else {
+            return Ok(());
+        };
+        let redirects = &redirects::with_aliases(redirects, &self.catalog);
+        let dest_base = self.dest_path.join("redirects");
+        fs::create_dir_all(&dest_base).at(&dest_base)?;
+        for (name, content) in [
+            ("nginx.conf", redirects::nginx(redirects)),
+            ("Caddyfile", redirects::caddy(redirects)),
+            ("_redirects", redirects::cloudflare(redirects)),
+        ] {
+            let dest = dest_base.join(name);
+            create(&dest, self.overwrite)?.write_all(content.as_bytes()).at(&dest)?;
+            let mut integrity = IntegrityBuilder::new();
+            integrity.update(content.as_bytes());
+            self.emitted.borrow_mut().record(manifest::File {
+                source: s!(REDIRECTS),
+                dest: s!("redirects/", name),
+                size: content.len(),
+                integrity: integrity.output().0,
+            });
+        }
+        Ok(())
+    }
 
-                use foundations::vec_ext;
-                use lighthtml::{*, prelude::*};
-                macro_rules! text {
-                    ($s:expr) => {
-                        vec![Text($s)]
-                    };
+    fn write_catalog(&self) -> error::Result<()> {
+        // replaced field by field, as `<a n ` becomes an attribute with quotes
+        let replace = |field: &mut Option<ByteString>| {
+            if let Some(s) = field {
+                *s = self.global_replacer.replace(s);
+            }
+        };
+        // readers of the catalog don't have a page to resolve protocol-relative urls against
+        let absolute = |url: ByteString| match url.strip_prefix("//") {
+            Some(rest) => cs!(env().scheme, "://", rest),
+            None => url,
+        };
+        let tools: Vec<_> = self.catalog.iter().cloned().map(|mut tool| {
+            replace(&mut tool.icon);
+            tool.icon = tool.icon.map(absolute);
+            replace(&mut tool.description);
+            replace(&mut tool.notice);
+            for link in &mut tool.links {
+                link.url = absolute(self.global_replacer.replace(&link.url));
+            }
+            tool
+        }).collect();
+        let catalog = catalog::Catalog { version: catalog::VERSION, commit: &self.commit, tools: &tools };
+        let content = serde_json::to_string_pretty(&catalog).unwrap();
+        let dest = self.dest_path.join("tools.json");
+        create(&dest, self.overwrite)?.write_all(content.as_bytes()).at(&dest)?;
+        let mut integrity = IntegrityBuilder::new();
+        integrity.update(content.as_bytes());
+        self.emitted.borrow_mut().record(manifest::File {
+            source: s!("page"),
+            dest: s!("tools.json"),
+            size: content.len(),
+            integrity: integrity.output().0,
+        });
+        Ok(())
+    }
+
+    /// writes `mirror-manifest.json`, every mirror key with its state and the owning tool
+    fn write_mirror_manifest(&self) -> error::Result<()> {
+        let content = serde_json::to_string_pretty(&catalog::MirrorManifest::new(&self.commit, &self.catalog)).unwrap();
+        let dest = self.dest_path.join("mirror-manifest.json");
+        create(&dest, self.overwrite)?.write_all(content.as_bytes()).at(&dest)?;
+        let mut integrity = IntegrityBuilder::new();
+        integrity.update(content.as_bytes());
+        self.emitted.borrow_mut().record(manifest::File {
+            source: s!("page"),
+            dest: s!("mirror-manifest.json"),
+            size: content.len(),
+            integrity: integrity.output().0,
+        });
+        Ok(())
+    }
+
+    /// writes a `sitemap.xml` into each dest dir with a `reg` (prod builds only)
+    ///
+    /// Every dir is the root of its own host, and there is no host serving them all for a sitemap index.
+    fn write_sitemap(&self) -> error::Result<()> {
+        let env = env();
+        if env.mode != Config::Prod {
+            return Ok(());
+        }
+        let page_dests = self.page_dests.borrow();
+        let mut pages: Vec<_> = page_dests.iter().collect();
+        pages.sort_by_key(|(page_name, _)| *page_name);
+        let mut dirs: Map<Vec<ByteString>> = Map::new();
+        for (page_name, dests) in pages {
+            for Dest { dir, name, reg, .. } in dests {
+                if reg == NO_REG {
+                    continue;
                 }
-                w!(&render_node(Element(E_HTML, vec_ext![
-                    @if let (Some(ref lang) = boot.lang) {
-                        (A_LANG, s!(lang))
-                    }
-                ], vec![
-                    Element(E_HEAD, attr!{}, vec_ext![
-                        Html(s!(&boot.head)),
-                        @for (css_content in boot.minified_css.iter()) {
-                            // TODO one tag?
-                            Element(E_STYLE, attr!{}, text!(s!(css_content)))
-                        },
-                        @for (jsldr::Resource { path, integrity } in boot.css.iter()) {
-                            Element(E_LINK, vec_ext![
-                                (A_REL, s!("stylesheet")),
-                                (A_HREF, s!(path)),
-                                @if let (Some(integrity) = integrity) {
-                                    (A_INTEGRITY, s!(integrity))
-                                },
-                                (A_CROSSORIGIN, s!("anonymous")),
-                            ], vec![])
-                        }
-                    ]),
-                    Element(E_BODY, attr!{}, vec_ext![
-                        Html(reg.replace_body(&boot.body)),
-                        @for ((key, data) in boot.includes.iter()) {
-                            // TODO one tag?
-                            Element(E_SCRIPT, attr!{}, text!(s!(
-                                "window.",
-                                key,
-                                "=",
-                                serde_json::to_string(data).unwrap()
-                            )))
+                let origin = env.origins.get(dir).ok_or_else(|| {
+                    Error::invalid(cs!("no origin for dest dir `", dir, "` of page `", page_name, "` in environment `", args().config, "`"))
+                        .at(self.base_path.join(SITE))
+                })?;
+                let url = cs!(origin.trim_end_matches('/'), "/", if name == "index.html" { "" } else { name });
+                let anchors: Vec<_> = self.anchors.get(page_name).into_iter().flatten().map(|anchor| cs!(url, "#", anchor)).collect();
+                let locs = dirs.entry(dir.clone()).or_default();
+                locs.push(url);
+                locs.extend(anchors);
+            }
+        }
+
+        let lastmod = self.commit_date.as_deref();
+        let source = SITE;
+        self.emitted.borrow_mut().forget(source);
+        for (dir, locs) in dirs {
+            let content = sitemap::urlset(&locs, lastmod);
+            let name = s!(dir, "/sitemap.xml");
+            let dest = self.dest_path.join(&name);
+            create(&dest, self.overwrite)?.write_all(content.as_bytes()).at(&dest)?;
+            let mut integrity = IntegrityBuilder::new();
+            integrity.update(content.as_bytes());
+            self.emitted.borrow_mut().record(manifest::File {
+                source: s!(source),
+                dest: name,
+                size: content.len(),
+                integrity: integrity.output().0,
+            });
+        }
+        Ok(())
+    }
+
+    fn write_manifest(&self) -> error::Result<()> {
+        let emitted = self.emitted.borrow();
+        let manifest = emitted.manifest(&self.commit, self.dirty, &args().config, env().mode.name());
+        let dest = self.dest_path.join("manifest.json");
+        serde_json::to_writer_pretty(create(&dest, self.overwrite)?, &manifest).at(dest)
+    }
+
+    fn build_all(&mut self, errors: &mut Errors) {
+        let dynamic_base = self.base_path.join("dynamic");
+
+        for dest in [&self.dest_code_base, &self.dest_page_boot_base] {
+            if let Err(e) = fs::create_dir_all(dest).at(dest) {
+                errors.push(e);
+                return;
+            }
+        }
+
+        for (file_name, path) in errors.collect(list_dir(dynamic_base.join("code"), false)).unwrap_or_default() {
+            if let Some(resource) = errors.collect(self.build_code(&file_name, &path)) {
+                self.code_info.first_insert(file_name, resource);
+            }
+        }
+
+        for (page_name, path) in errors.collect(list_dir(dynamic_base.join("page"), true)).unwrap_or_default() {
+            if let Some(sources) = errors.collect(self.build_page(&page_name, &path)) {
+                self.page_sources.first_insert(page_name, sources);
+            }
+        }
+
+        self.build_statics(errors);
+        let _ = errors.collect(self.write_redirects());
+        let _ = errors.collect(self.write_catalog());
+        let _ = errors.collect(self.write_mirror_manifest());
+        let _ = errors.collect(self.write_sitemap());
+        let _ = errors.collect(self.write_manifest());
+    }
+
+    /// rebuilds only the outputs affected by the changed source files, returning the names of the rebuilt pages
+    fn rebuild(&mut self, changed: &[PathBuf], errors: &mut Errors) -> Vec<ByteString> {
+        let mut sources = Vec::new();
+        let mut pages = Vec::new();
+        for path in changed {
+            let Ok(rel) = path.strip_prefix(self.base_path) else { continue };
+            let mut components = rel.iter().map(|c| c.to_str().unwrap_or_default());
+            let source = match (components.next(), components.next(), components.next()) {
+                (Some("page"), _, _) => Source::PageData,
+                (Some("fragment"), Some(name), None) => Source::Fragment(s!(name)),
+                (Some("dynamic"), Some("code"), Some(name)) => Source::Code(s!(name)),
+                (Some("static"), Some(name), None) => Source::Static(s!(name)),
+                (Some("dynamic"), Some("page"), Some(name)) => {
+                    pages.push(s!(name));
+                    continue;
+                },
+                _ => continue,
+            };
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+
+        let mut rebuilt_sources = Vec::new();
+        for source in sources {
+            let ok = match &source {
+                Source::PageData => {
+                    let mut codegen_inserts = Inserts::new();
+                    let mut includes = Map::new();
+                    let mut anchors = Map::new();
+                    let mut catalog = Vec::new();
+                    match codegen(&mut codegen_inserts, &mut includes, &mut anchors, &mut catalog, self.base_path.join("page")) {
+                        Ok(()) => {
+                            self.codegen_inserts = codegen_inserts;
+                            self.includes = includes;
+                            self.anchors = anchors;
+                            self.catalog = catalog;
+                            true
                         },
-                        @for (js_content in boot.minified_js.iter()) {
-                            Element(E_SCRIPT, attr!{}, text!(s!(js_content)))
+                        Err(codegen_errors) => {
+                            errors.extend(codegen_errors);
+                            false
                         },
-                        @for (jsldr::Resource { path, integrity } in boot.js.iter()) {
-                            Element(E_SCRIPT, vec_ext![
-                                (A_HREF, s!(path)),
-                                @if let (Some(integrity) = integrity) {
-                                    (A_INTEGRITY, s!(integrity))
-                                },
-                                (A_CROSSORIGIN, s!("anonymous")),
-                            ], vec![])
+                    }
+                },
+                Source::Fragment(file_name) => {
+                    let path = self.base_path.join("fragment").join(file_name);
+                    if !path.is_file() {
+                        let _ = self.fragments.shift_remove(file_name);
+                        let _ = self.minifieds.shift_remove(file_name);
+                        true
+                    } else {
+                        match errors.collect(build_fragment(file_name, &path, &self.icpreg_map)) {
+                            Some(Fragment::Insert(content)) => {
+                                let _ = self.fragments.insert(file_name.clone(), content);
+                                true
+                            },
+                            Some(Fragment::Minified(content)) => {
+                                let _ = self.minifieds.insert(file_name.clone(), content);
+                                true
+                            },
+                            None => false,
                         }
-                    ]),
-                ])));
-            */
+                    }
+                },
+                // read again by `build_statics` and `build_page`
+                Source::Static(_) => true,
+                Source::Code(file_name) => {
+                    let path = self.base_path.join("dynamic/code").join(file_name);
+                    if !path.is_file() {
+                        let _ = self.code_info.shift_remove(file_name);
+                        self.emitted.borrow_mut().forget(&s!("dynamic/code/", file_name));
+                        true
+                    } else if let Some(resource) = errors.collect(self.build_code(file_name, &path)) {
+                        let _ = self.code_info.insert(file_name.clone(), resource);
+                        true
+                    } else {
+                        false
+                    }
+                },
+            };
+            if ok {
+                rebuilt_sources.push(source);
+            }
+        }
 
+        for (page_name, page_sources) in &self.page_sources {
+            if page_sources.iter().any(|source| rebuilt_sources.contains(source)) {
+                pages.push(page_name.clone());
+            }
+        }
+        // pages that failed before are always retried
+        let dynamic_page_base = self.base_path.join("dynamic/page");
+        for (page_name, _) in errors.collect(list_dir(&dynamic_page_base, true)).unwrap_or_default() {
+            if !self.page_sources.contains_key(&page_name) {
+                pages.push(page_name);
+            }
+        }
+        pages.sort();
+        pages.dedup();
+
+        let mut rebuilt = Vec::new();
+        for page_name in pages {
+            let path = dynamic_page_base.join(&page_name);
+            if !path.is_dir() {
+                let _ = self.page_sources.shift_remove(&page_name);
+                let _ = self.page_dests.borrow_mut().shift_remove(&page_name);
+                self.emitted.borrow_mut().forget(&s!("dynamic/page/", page_name));
+                continue;
+            }
+            match errors.collect(self.build_page(&page_name, &path)) {
+                Some(sources) => {
+                    let _ = self.page_sources.insert(page_name.clone(), sources);
+                    rebuilt.push(page_name);
+                },
+                None => {
+                    let _ = self.page_sources.shift_remove(&page_name);
+                    let _ = self.page_dests.borrow_mut().shift_remove(&page_name);
+                },
             }
         }
+
+        // static files are cheap, and may use any insert
+        if !rebuilt_sources.is_empty() {
+            // removed ones are forgotten by rebuilding from scratch
+            self.emitted.borrow_mut().forget_prefix("static/");
+            self.build_statics(errors);
+        }
+        if rebuilt_sources.contains(&Source::PageData) {
+            // the keys of the renamed tools are exported along with their current ones
+            let _ = errors.collect(self.write_redirects());
+            let _ = errors.collect(self.write_catalog());
+            let _ = errors.collect(self.write_mirror_manifest());
+        }
+        let _ = errors.collect(self.write_sitemap());
+        let _ = errors.collect(self.write_manifest());
+        rebuilt
     }
 }
 
-// TODO build frameworks
+pub fn build(args: Args) -> Result<(), Errors> {
+    let args = ARGS.get_or_init(|| args);
+    let _ = ENV.set(load_env(args)?);
+    match &args.command {
+        None => build_once(args),
+        Some(Command::Watch(watch_args)) => watch::watch(args, watch_args),
+        Some(Command::Serve(serve_args)) => serve::serve(args, serve_args),
+    }
+}
+
+/// a path beside the dest, e.g. `dist/` -> `.dist.tmp`
+fn beside_dest(dest_path: &Path, suffix: &str) -> error::Result<PathBuf> {
+    let name = dest_path.file_name().ok_or_else(|| Error::invalid("dest path has no file name").at(dest_path))?;
+    Ok(dest_path.with_file_name(cs!(".", name.to_string_lossy(), ".", suffix)))
+}
+
+fn remove_dir<P: AsRef<Path>>(path: P) -> error::Result<()> {
+    let path = path.as_ref();
+    if path.exists() {
+        fs::remove_dir_all(path).at(path)?;
+    }
+    Ok(())
+}
+
+/// refuses to remove or replace a dest containing the sources, e.g. `-d .`
+fn check_dest_removable(dest_path: &Path, base_path: &Path) -> error::Result<()> {
+    let Ok(dest) = dest_path.canonicalize() else {
+        // missing, so it contains nothing
+        return Ok(());
+    };
+    let base = base_path.canonicalize().at(base_path)?;
+    if base.starts_with(&dest) {
+        return Err(Error::invalid(cs!("dest contains the source path ", base.to_string_lossy(), ", refusing to remove it")).at(dest_path));
+    }
+    Ok(())
+}
+
+/// the prefix of the builds kept beside the dest by `--atomic`, e.g. `dist/` -> `.dist.build-`
+fn atomic_build_prefix(dest_path: &Path) -> error::Result<String> {
+    let name = dest_path.file_name().ok_or_else(|| Error::invalid("dest path has no file name").at(dest_path))?;
+    Ok(cs!(".", name.to_string_lossy(), ".build-"))
+}
+
+/// `--atomic` keeps the dest as a symlink to the latest build beside it, so that it's swapped by renaming a new symlink over it
+#[cfg(unix)]
+fn swap_in(dest_path: &Path, build_path: &Path) -> error::Result<()> {
+    let link_path = beside_dest(dest_path, "link")?;
+    if link_path.symlink_metadata().is_ok() {
+        fs::remove_file(&link_path).at(&link_path)?;
+    }
+    let previous = fs::read_link(dest_path).ok();
+    // relative to the dir containing both
+    std::os::unix::fs::symlink(build_path.file_name().unwrap(), &link_path).at(&link_path)?;
+    fs::rename(&link_path, dest_path).at(&link_path)?;
+    if let Some(previous) = previous {
+        let prefix = atomic_build_prefix(dest_path)?;
+        if previous.file_name().is_some_and(|name| name.to_string_lossy().starts_with(&prefix)) {
+            remove_dir(dest_path.parent().unwrap_or(Path::new("")).join(previous))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn swap_in(dest_path: &Path, _build_path: &Path) -> error::Result<()> {
+    Err(Error::invalid("--atomic needs symlinks, which are only used on unix").at(dest_path))
+}
+
+fn build_once(args: &Args) -> Result<(), Errors> {
+    let Args { dest_path, clean, overwrite, atomic, .. } = args;
+    if [*clean, *overwrite, *atomic].into_iter().filter(|mode| *mode).count() > 1 {
+        return Err(Error::invalid("only one of --clean, --overwrite and --atomic can be given").into());
+    }
+    if *clean || *atomic {
+        check_dest_removable(dest_path, &args.base_path)?;
+    }
+    let build_path = if *atomic {
+        if dest_path.exists() && fs::read_link(dest_path).is_err() {
+            return Err(Error::invalid("--atomic replaces the dest with a symlink, remove the existing dest dir once first").at(dest_path).into());
+        }
+        let prefix = atomic_build_prefix(dest_path)?;
+        let parent = dest_path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        // left by interrupted builds, but not the one the dest points to
+        let current = fs::read_link(dest_path).ok().and_then(|target| target.file_name().map(|name| name.to_owned()));
+        for entry in fs::read_dir(parent).at(parent)?.flatten() {
+            let name = entry.file_name();
+            if name.to_string_lossy().starts_with(&prefix) && Some(&name) != current.as_ref() {
+                remove_dir(entry.path())?;
+            }
+        }
+        let id = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis();
+        dest_path.with_file_name(cs!(prefix, id.to_string()))
+    } else {
+        dest_path.clone()
+    };
+
+    // nothing is written to the dest if the sources are broken
+    let mut builder = Builder::new(args, &build_path, *overwrite)?;
+    if *clean {
+        remove_dir(dest_path)?;
+    }
+    let mut errors = Errors::new();
+    builder.build_all(&mut errors);
+
+    if let Some(assets_path) = &args.assets_path {
+        let (asset_errors, unused) = builder.asset_refs.borrow().check(assets_path);
+        errors.extend(asset_errors);
+        for path in unused {
+            eprintln!("warning: unused icon `{}`", path);
+        }
+    }
+
+    match &builder.redirects {
+        Some(redirects) => {
+            let (redirect_errors, unused) = builder.redirect_refs.borrow().check(redirects, &args.base_path.join(REDIRECTS));
+            errors.extend(redirect_errors);
+            for key in unused {
+                eprintln!("warning: unused redirect key `{}`", key);
+            }
+        },
+        None => eprintln!("warning: {} not found, the redirect keys are not checked", REDIRECTS),
+    }
+
+    if *atomic {
+        if !errors.is_empty() {
+            let _ = errors.collect(remove_dir(&build_path));
+            return errors.check();
+        }
+        swap_in(dest_path, &build_path)?;
+    }
+
+    errors.check()
+}
@@ -1,3 +1,7 @@
+// TODO build frameworks
 fn main() {
-    ldtstore_codegen::build(argh::from_env());
+    if let Err(errors) = ldtstore_codegen::build(argh::from_env()) {
+        eprintln!("{}", errors);
+        std::process::exit(1);
+    }
 }
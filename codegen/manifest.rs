@@ -0,0 +1,52 @@
+use serde::Serialize;
+use crate::{ByteString, Map};
+
+/// `manifest.json` in the dest
+#[derive(Debug, Serialize)]
+pub struct Manifest<'a> {
+    pub commit: &'a str,
+    pub dirty: bool,
+    pub codegen_commit: &'static str,
+    pub environment: &'a str,
+    pub config: &'static str,
+    pub files: Vec<&'a File>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct File {
+    /// relative to the source path, e.g. `dynamic/code/main.ts` or `dynamic/page/tool`
+    pub source: ByteString,
+    /// relative to the dest path
+    pub dest: ByteString,
+    pub size: usize,
+    pub integrity: String,
+}
+
+/// the emitted files by dest
+#[derive(Debug, Default)]
+pub struct Files(Map<File>);
+
+impl Files {
+    pub fn new() -> Files {
+        Files(Map::new())
+    }
+
+    /// forgets everything emitted from `source` before, as a rebuild may emit under other names
+    pub fn forget(&mut self, source: &str) {
+        self.0.retain(|_, file| file.source != source);
+    }
+
+    pub fn forget_prefix(&mut self, prefix: &str) {
+        self.0.retain(|_, file| !file.source.starts_with(prefix));
+    }
+
+    pub fn record(&mut self, file: File) {
+        let _ = self.0.insert(file.dest.clone(), file);
+    }
+
+    pub fn manifest<'a>(&'a self, commit: &'a str, dirty: bool, environment: &'a str, config: &'static str) -> Manifest<'a> {
+        let mut files: Vec<&File> = self.0.values().collect();
+        files.sort_by(|a, b| a.dest.cmp(&b.dest));
+        Manifest { commit, dirty, codegen_commit: env!("GIT_HASH"), environment, config, files }
+    }
+}
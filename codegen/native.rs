@@ -0,0 +1,78 @@
+use std::path::Path;
+use crate::error::{self, Error, ResultExt};
+
+fn invalid<E: ToString>(what: &str, e: E) -> Error {
+    Error::invalid(s!(what, ": ", e.to_string()))
+}
+
+/// same as `esbuild --minify` on a css file
+pub fn minify_css(full_path: &Path) -> error::Result<String> {
+    use lightningcss::stylesheet::{StyleSheet, ParserOptions, MinifyOptions, PrinterOptions};
+    let source = std::fs::read_to_string(full_path).at(full_path)?;
+    let mut sheet = StyleSheet::parse(&source, ParserOptions {
+        filename: full_path.to_string_lossy().into_owned(),
+        ..Default::default()
+    }).map_err(|e| invalid("failed to parse css", e).at(full_path))?;
+    sheet.minify(MinifyOptions::default()).map_err(|e| invalid("failed to minify css", e).at(full_path))?;
+    let output = sheet.to_css(PrinterOptions {
+        minify: true,
+        ..Default::default()
+    }).map_err(|e| invalid("failed to print css", e).at(full_path))?;
+    Ok(s!(output.code, "\n"))
+}
+
+/// same as `esbuild --format=iife --target=es6 --charset=utf8` on a ts file,
+/// plus `--minify-whitespace --minify-syntax` if `minify`
+pub fn compile_script(full_path: &Path, minify: bool) -> error::Result<String> {
+    use oxc::{
+        allocator::Allocator,
+        ast::ast::ModuleDeclaration,
+        codegen::{Codegen, CodegenOptions},
+        minifier::{CompressOptions, Minifier, MinifierOptions},
+        parser::Parser,
+        semantic::SemanticBuilder,
+        span::{GetSpan, SourceType},
+        syntax::es_target::ESTarget,
+        transformer::{TransformOptions, Transformer},
+    };
+
+    let source = std::fs::read_to_string(full_path).at(full_path)?;
+    let allocator = Allocator::default();
+    let source_type = SourceType::from_path(full_path).map_err(|e| invalid("unknown script type", e).at(full_path))?;
+
+    let parsed = Parser::new(&allocator, &source, source_type).parse();
+    if let Some(e) = parsed.errors.first() {
+        return Err(invalid("failed to parse script", e).at(full_path));
+    }
+    let mut program = parsed.program;
+    // the scripts are only modules for the type checker (`export {};`), the iife wrapper replaces that,
+    // but any other import or export would be lost in it
+    for decl in program.body.iter().filter_map(|stmt| stmt.as_module_declaration()) {
+        let is_empty_export = matches!(decl, ModuleDeclaration::ExportNamedDeclaration(export)
+            if export.declaration.is_none() && export.specifiers.is_empty() && export.source.is_none());
+        if !is_empty_export {
+            let text = decl.span().source_text(&source);
+            return Err(Error::invalid(s!("unsupported `", text, "`, only `export {};` is allowed in scripts")).at(full_path));
+        }
+    }
+    program.body.retain(|stmt| !stmt.is_module_declaration());
+
+    let scoping = SemanticBuilder::new().build(&program).semantic.into_scoping();
+    let options = TransformOptions::from_target("es2015").map_err(|e| invalid("invalid target", e).at(full_path))?;
+    let transformed = Transformer::new(&allocator, full_path, &options).build_with_scoping(scoping, &mut program);
+    if let Some(e) = transformed.errors.first() {
+        return Err(invalid("failed to transform script", e).at(full_path));
+    }
+
+    let code = if minify {
+        let minified = Minifier::new(MinifierOptions {
+            // esbuild is not asked for `--minify-identifiers`
+            mangle: None,
+            compress: Some(CompressOptions { target: ESTarget::ES2015, ..CompressOptions::default() }),
+        }).minify(&allocator, &mut program);
+        Codegen::new().with_options(CodegenOptions::minify()).with_scoping(minified.scoping).build(&program).code
+    } else {
+        Codegen::new().build(&program).code
+    };
+    Ok(if minify { s!("(()=>{", code, "})();\n") } else { s!("(() => {\n", code, "})();\n") })
+}
@@ -0,0 +1,181 @@
+use std::path::Path;
+use crate::{ByteString, Map, catalog, error::{Error, Errors}};
+
+const PREFIX: &str = "{{REDIRECT}}/";
+
+/// the paths handled by the redirect service, see `resolve_links` and `tile_inner`
+const KINDS: [&str; 3] = ["r", "r2", "mirror"];
+
+/// `redirects.yml`, `{r|r2|mirror}/{key}` -> target url
+pub type Redirects = Map<ByteString>;
+
+/// the status of every exported redirect, as the targets follow the latest versions
+const STATUS: &str = "302";
+
+/// redirect keys (e.g. `r2/dismxx`) linked to by the generated files, with the names of the pages or code files linking to them
+#[derive(Debug, Default)]
+pub struct RedirectRefs(Map<Vec<ByteString>>);
+
+impl RedirectRefs {
+    pub fn new() -> RedirectRefs {
+        RedirectRefs(Map::new())
+    }
+
+    pub fn scan(&mut self, content: &str, source: &str) {
+        for (start, _) in content.match_indices(PREFIX) {
+            let rest = &content[(start + PREFIX.len())..];
+            let end = rest.find(|c: char| matches!(c, '"' | '\'' | '`' | '(' | ')' | '<' | '>') || c.is_whitespace()).unwrap_or(rest.len());
+            let key = rest[..end].trim_end_matches('/');
+            // placeholders in the explanations (e.g. `r2/{name}` in the plain page) aren't links
+            let Some((kind, name)) = key.split_once('/') else { continue };
+            if !KINDS.contains(&kind) || name.is_empty() || name.contains(['{', '$', '/']) {
+                continue;
+            }
+            let sources = self.0.entry(s!(key)).or_default();
+            if !sources.iter().any(|s| s == source) {
+                sources.push(s!(source));
+            }
+        }
+    }
+
+    /// returns the keys missing in `redirects` as errors, and the keys in it that are never linked to
+    pub fn check(&self, redirects: &Redirects, redirects_path: &Path) -> (Errors, Vec<ByteString>) {
+        let mut errors = Errors::new();
+        for (key, sources) in &self.0 {
+            if !redirects.contains_key(key) {
+                errors.push(Error::invalid(s!("no target for redirect key `", key, "` linked to by ", sources.join(", "))).at(redirects_path));
+            }
+        }
+        let unused = redirects.keys().filter(|key| !self.0.contains_key(*key)).cloned().collect();
+        (errors, unused)
+    }
+}
+
+/// whether `name` can be written unquoted in every exported format, where e.g. `;` or `{` would end
+/// an nginx `map` entry early and `#` would start a comment
+pub fn is_key_name(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'~' | b'-'))
+}
+
+/// checks that every entry can be written to all the exported formats
+pub fn validate(redirects: &Redirects, redirects_path: &Path) -> Errors {
+    let mut errors = Errors::new();
+    for (key, target) in redirects {
+        let valid_key = key.split_once('/').is_some_and(|(kind, name)| KINDS.contains(&kind) && is_key_name(name));
+        if !valid_key {
+            errors.push(Error::invalid(s!(
+                "malformed redirect key, expected `r/{key}`, `r2/{key}` or `mirror/{key}` with a key of letters, digits, `.`, `_`, `~` and `-`"
+            )).at(redirects_path).path(key.clone()));
+        }
+        // quoted for nginx, where `$` would still be a variable, and bare for the others
+        let unsafe_char = |c: char| c.is_whitespace() || c.is_control() || matches!(c, '"' | '\\' | '$' | '{' | '}');
+        if !(target.starts_with("https://") || target.starts_with("http://")) || target.contains(unsafe_char) {
+            errors.push(Error::invalid(s!("malformed redirect target `", target, "`, expected an absolute url")).at(redirects_path).path(key.clone()));
+        }
+    }
+    errors
+}
+
+/// adds the keys derived from the former names of the renamed tools (e.g. `r2/{alias}-d-{link}`),
+/// redirected to the targets of their current keys unless `redirects.yml` gives them their own
+pub fn with_aliases(redirects: &Redirects, tools: &[catalog::Tool]) -> Redirects {
+    let mut res = redirects.clone();
+    for tool in tools {
+        for link in &tool.links {
+            let (Some(suffix), Some(target)) = (link.key.strip_prefix(tool.name.as_str()), redirects.get(&s!(link.link_type, "/", link.key))) else {
+                continue;
+            };
+            for alias in &tool.aliases {
+                let _ = res.entry(s!(link.link_type, "/", alias, suffix)).or_insert_with(|| target.clone());
+            }
+        }
+    }
+    res
+}
+
+/// every key is redirected both with and without the trailing slash, as the tiles link to `r/{key}/`
+fn paths(key: &str) -> [ByteString; 2] {
+    [s!("/", key), s!("/", key, "/")]
+}
+
+/// an nginx `map` from `$uri` to `$ldtstore_redirect`, used as
+/// `if ($ldtstore_redirect) { return 302 $ldtstore_redirect; }`
+pub fn nginx(redirects: &Redirects) -> ByteString {
+    let mut res = s!("map $uri $ldtstore_redirect {\n    default \"\";\n");
+    for (key, target) in redirects {
+        for path in paths(key) {
+            res.push_str(&s!("    ", path, " \"", target, "\";\n"));
+        }
+    }
+    res.push_str("}\n");
+    res
+}
+
+/// `redir` directives, to be imported in the site block of the redirect service
+pub fn caddy(redirects: &Redirects) -> ByteString {
+    let mut res = ByteString::new();
+    for (key, target) in redirects {
+        for path in paths(key) {
+            res.push_str(&s!("redir ", path, " ", target, " ", STATUS, "\n"));
+        }
+    }
+    res
+}
+
+/// a Cloudflare Pages `_redirects` file
+pub fn cloudflare(redirects: &Redirects) -> ByteString {
+    let mut res = ByteString::new();
+    for (key, target) in redirects {
+        for path in paths(key) {
+            res.push_str(&s!(path, " ", target, " ", STATUS, "\n"));
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(entries: &[(&str, &str)]) -> usize {
+        let redirects: Redirects = entries.iter().map(|(key, target)| (s!(*key), s!(*target))).collect();
+        validate(&redirects, Path::new("redirects.yml")).iter().count()
+    }
+
+    #[test]
+    fn accepts_plain_keys() {
+        assert_eq!(errors(&[("r2/dismxx-ghr", "https://github.com/Chuyu-Team/Dism-Multi-language/releases"), ("mirror/cpu-z_2.1~x64", "https://example.com/a?b=c&d")]), 0);
+    }
+
+    #[test]
+    fn rejects_hostile_keys() {
+        for key in [
+            "r2/x;}server{listen 80;",
+            "r2/x \"y\"",
+            "r2/x'y",
+            "r2/x#y",
+            "r2/x\\y",
+            "r2/x{y}",
+            "r2/x\ny",
+            "r2/x/y",
+            "r2/",
+            "x/y",
+        ] {
+            assert_eq!(errors(&[(key, "https://example.com/")]), 1, "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn rejects_hostile_targets() {
+        for target in ["https://example.com/\";}", "https://example.com/$host", "https://example.com/{path}", "https://example.com/ x", "//example.com/"] {
+            assert_eq!(errors(&[("r2/x", target)]), 1, "{:?}", target);
+        }
+    }
+
+    #[test]
+    fn exported_lines_stay_whole() {
+        let redirects: Redirects = [(s!("r2/a.b_c~d-1"), s!("https://example.com/x?y=1"))].into_iter().collect();
+        assert_eq!(nginx(&redirects), "map $uri $ldtstore_redirect {\n    default \"\";\n    /r2/a.b_c~d-1 \"https://example.com/x?y=1\";\n    /r2/a.b_c~d-1/ \"https://example.com/x?y=1\";\n}\n");
+        assert_eq!(caddy(&redirects).lines().count(), 2);
+    }
+}
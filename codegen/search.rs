@@ -0,0 +1,113 @@
+use pinyin::ToPinyin;
+use crate::{ByteString, data::{ToolSearchField, ToolSearchKey}};
+
+pub const WEIGHT_TITLE: u8 = 5;
+pub const WEIGHT_KEYWORDS: u8 = 4;
+pub const WEIGHT_DESCRIPTION: u8 = 3;
+pub const WEIGHT_NOTICE: u8 = 2;
+pub const WEIGHT_LINK_TITLES: u8 = 1;
+
+/// a search key for `text`, with every Chinese character also spelled in full pinyin and by its initial,
+/// so that e.g. 图吧工具箱 can be found by `tuba` or `tbgj`
+pub fn search_key(text: &str) -> ToolSearchKey {
+    let text = text.to_lowercase();
+    let mut full = ByteString::new();
+    let mut initials = ByteString::new();
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        match c.to_pinyin() {
+            Some(p) => {
+                let plain = p.plain();
+                full.push_str(plain);
+                initials.push_str(&plain[..1]);
+            },
+            None => {
+                full.extend(c.to_lowercase());
+                initials.extend(c.to_lowercase());
+            },
+        }
+    }
+    if full == text {
+        return ToolSearchKey { text, pinyin: None, initials: None };
+    }
+    ToolSearchKey { text, pinyin: Some(full), initials: Some(initials) }
+}
+
+/// a search key matched by `text` only, for the fields below the keywords
+fn text_key(text: &str) -> ToolSearchKey {
+    ToolSearchKey { text: text.to_lowercase(), pinyin: None, initials: None }
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '+' | '-' | '_' | '.' | '#')
+}
+
+/// splits `text` at whitespace and punctuation, skipping html tags, so that every clause
+/// of a description can be matched by prefix on its own
+pub fn tokenize(text: &str) -> Vec<ByteString> {
+    let mut tokens = Vec::new();
+    let mut token = ByteString::new();
+    let mut in_tag = false;
+    for c in text.chars().chain([' ']) {
+        if in_tag {
+            in_tag = c != '>';
+        } else if is_token_char(c) {
+            token.push(c);
+        } else {
+            if !token.is_empty() {
+                tokens.push(std::mem::take(&mut token));
+            }
+            in_tag = c == '<';
+        }
+    }
+    tokens
+}
+
+/// the tokens of `texts`, spelled in pinyin only from the keywords up, which keeps `__DATA__` small
+pub fn field<'a, I: IntoIterator<Item = &'a str>>(weight: u8, texts: I) -> Option<ToolSearchField> {
+    let key = if weight >= WEIGHT_KEYWORDS { search_key } else { text_key };
+    let tokens: Vec<_> = texts.into_iter().flat_map(tokenize).map(|token| key(&token)).collect();
+    (!tokens.is_empty()).then_some(ToolSearchField { weight, tokens })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_key_spells_chinese() {
+        let key = search_key("图吧工具箱");
+        assert_eq!(key.text, "图吧工具箱");
+        assert_eq!(key.pinyin.as_deref(), Some("tubagongjuxiang"));
+        assert_eq!(key.initials.as_deref(), Some("tbgjx"));
+
+        let key = search_key("图吧Toolbox");
+        assert_eq!(key.text, "图吧toolbox");
+        assert_eq!(key.pinyin.as_deref(), Some("tubatoolbox"));
+        assert_eq!(key.initials.as_deref(), Some("tbtoolbox"));
+    }
+
+    #[test]
+    fn search_key_skips_plain_text() {
+        let key = search_key("CPU-Z");
+        assert_eq!(key.text, "cpu-z");
+        assert!(key.pinyin.is_none());
+        assert!(key.initials.is_none());
+    }
+
+    #[test]
+    fn tokenize_skips_tags_and_punctuation() {
+        let tokens = tokenize("起源于<a n href=\"//example.com/\">图吧</a>，集合了 CPU-Z 等工具。");
+        assert_eq!(tokens, ["起源于", "图吧", "集合了", "CPU-Z", "等工具"]);
+        assert!(tokenize(" ，<br> ").is_empty());
+    }
+
+    #[test]
+    fn field_spells_only_from_keywords() {
+        let title = field(WEIGHT_TITLE, ["图吧工具箱"]).unwrap();
+        assert_eq!(title.tokens[0].pinyin.as_deref(), Some("tubagongjuxiang"));
+        let description = field(WEIGHT_DESCRIPTION, ["图吧 工具箱"]).unwrap();
+        assert_eq!(description.tokens.len(), 2);
+        assert!(description.tokens.iter().all(|token| token.pinyin.is_none() && token.initials.is_none()));
+        assert!(field(WEIGHT_NOTICE, ["", "。"]).is_none());
+    }
+}
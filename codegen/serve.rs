@@ -0,0 +1,194 @@
+use std::path::Path;
+use crate::{Args, ByteString, Map, env, load_yaml, config::ToolGroup, codegen::{resolve_links, tool_link_title}, error::{self, Error, Errors}};
+
+/// serve the dest over HTTP to preview a build, with local stand-ins for the asset and redirect services
+#[derive(argh::FromArgs)]
+#[argh(subcommand, name = "serve")]
+pub struct ServeArgs {
+    /// address to listen on (default 127.0.0.1:8080)
+    #[argh(option, default = "String::from(\"127.0.0.1:8080\")")]
+    addr: String,
+}
+
+const REDIRECT_PREFIXES: [&str; 3] = ["r", "r2", "mirror"];
+
+struct LinkInfo {
+    tool: ByteString,
+    tool_title: ByteString,
+    title: ByteString,
+}
+
+/// `{link_type}/{link}` -> link, read again on every request so that it follows the sources
+fn link_table(base_path: &Path) -> error::Result<Map<LinkInfo>> {
+    let groups: Vec<ToolGroup> = load_yaml(base_path.join("page/tool/tools.yml"))?;
+    let mut table = Map::new();
+    for group in groups {
+        for tool in group.list {
+            for link in resolve_links(&tool.name, &tool.links).iter() {
+                let _ = table.insert(s!(link.link_type.as_str(), "/", link.link), LinkInfo {
+                    tool: tool.name.clone(),
+                    tool_title: tool.title.clone(),
+                    title: tool_link_title(link.title.clone()),
+                });
+            }
+        }
+    }
+    Ok(table)
+}
+
+fn content_type(path: &str) -> &'static str {
+    match path.rsplit('.').next().unwrap_or_default() {
+        "html" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "txt" => "text/plain; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "webp" => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+fn page(title: &str, body: &str) -> Vec<u8> {
+    s!("<!DOCTYPE html>\n<html>\n<head><meta charset=\"UTF-8\"><title>", title, "</title></head>\n<body>\n", body, "</body>\n</html>\n").into_bytes()
+}
+
+fn escape(text: &str) -> ByteString {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn redirect(base_path: &Path, prefix: &str, key: &str) -> (u16, Vec<u8>) {
+    let full_key = s!(prefix, "/", key);
+    let shown = escape(&full_key);
+    let redirect = &env().redirect;
+    let scheme = if redirect.starts_with("//") { "https:" } else { "" };
+    let production = s!("<p>production: <code>", scheme, redirect, "/", shown, "</code></p>\n");
+    if prefix == "r" {
+        return (200, page(&shown, &s!("<h1>", shown, "</h1>\n<p>not a tool link, resolved by the redirect service only</p>\n", production)));
+    }
+    match link_table(base_path) {
+        Ok(table) => match table.get(&full_key) {
+            Some(LinkInfo { tool, tool_title, title }) => (200, page(&shown, &s!(
+                "<h1>", shown, "</h1>\n",
+                "<p>tool: <a href=\"/tool/plain.html#", escape(tool), "\">", escape(tool_title), "</a> <i>", escape(tool), "</i></p>\n",
+                "<p>link: ", escape(title), "</p>\n",
+                production
+            ))),
+            None => (404, page(&shown, &s!("<h1>", shown, "</h1>\n<p>unknown redirect key</p>\n"))),
+        },
+        Err(e) => (500, page(&shown, &s!("<h1>", shown, "</h1>\n<pre>", escape(&e.to_string()), "</pre>\n"))),
+    }
+}
+
+/// drops the integrity of the code files, which no longer matches once the hosts in them are rewritten
+fn strip_integrity(text: &str, pattern: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(pattern) {
+        res.push_str(&rest[..start]);
+        let value = &rest[(start + pattern.len())..];
+        rest = &value[value.find('"').map_or(value.len(), |end| end + 1)..];
+    }
+    res.push_str(rest);
+    res
+}
+
+/// the dest dir served as the root of `{dir}.localhost`, as each one is the root of its own host in production
+///
+/// Only the `dirs` found in the dest are accepted, so the host can't name a path outside of it.
+fn host_dir<'d>(dirs: &'d [ByteString], host: Option<&str>) -> Option<&'d ByteString> {
+    let name = host?.split(':').next()?.strip_suffix(".localhost")?;
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return None;
+    }
+    dirs.iter().find(|dir| *dir == name)
+}
+
+/// `hosts` are the production hosts and what they are replaced with, `dirs` the dest dirs served on their own hosts
+fn handle(args: &Args, hosts: &[(ByteString, ByteString)], dirs: &[ByteString], host: Option<&str>, url: &str) -> (u16, &'static str, Vec<u8>) {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+    if segments.contains(&"..") {
+        return (400, "text/plain", b"bad request".to_vec());
+    }
+
+    if let Some(prefix) = segments.first().filter(|prefix| REDIRECT_PREFIXES.contains(prefix)) {
+        let (status, body) = redirect(&args.base_path, prefix, &segments[1..].join("/"));
+        return (status, content_type(".html"), body);
+    }
+
+    // the shared files (`code/`, `image/` ...) are still found on the hosts of the dest dirs, as with relative `assets`
+    let dir_path = host_dir(dirs, host).map(|dir| args.dest_path.join(dir)).filter(|dir_path| dir_path.join(segments.join("/")).exists());
+    let mut full_path = match (dir_path, &args.assets_path, segments.first()) {
+        (Some(dir_path), _, _) => dir_path,
+        (None, Some(assets_path), Some(&"image")) => assets_path.clone(),
+        _ => args.dest_path.clone(),
+    };
+    full_path.extend(&segments);
+    if full_path.is_dir() {
+        full_path.push("index.html");
+    }
+    let file_name = full_path.to_string_lossy().into_owned();
+    match std::fs::read(&full_path) {
+        Ok(content) => {
+            let ty = content_type(&file_name);
+            let content = if ty.starts_with("text/") || ty == "application/json" {
+                let mut text = String::from_utf8_lossy(&content).into_owned();
+                for (host, local) in hosts {
+                    text = text.replace(host.as_str(), local);
+                }
+                text = match ty {
+                    "text/html; charset=utf-8" => strip_integrity(&text, " integrity=\""),
+                    "application/json" => strip_integrity(&text, ",\"integrity\":\""),
+                    _ => text,
+                };
+                text.into_bytes()
+            } else {
+                content
+            };
+            (200, ty, content)
+        },
+        Err(_) => (404, "text/plain", b"not found".to_vec()),
+    }
+}
+
+pub fn serve(args: &Args, ServeArgs { addr }: &ServeArgs) -> Result<(), Errors> {
+    // links to the hosts of the environment are pointed to this server,
+    // and the subdomains named after a dest dir to `{dir}.localhost` on it
+    let env = env();
+    let port = addr.rsplit_once(':').map_or("80", |(_, port)| port);
+    let mut hosts: Vec<(ByteString, ByteString)> = [&env.redirect, &env.assets].into_iter()
+        .filter(|base| base.starts_with("//"))
+        .map(|base| (s!(base, "/"), s!("//", addr, "/")))
+        .collect();
+    let mut dirs = Vec::new();
+    for entry in std::fs::read_dir(&args.dest_path).map_err(|e| Error::invalid(s!(e.to_string())).at(&args.dest_path))?.flatten() {
+        // the dest dirs, not `code/` or `page-boot/`
+        if entry.path().join("index.html").is_file() {
+            dirs.push(s!(entry.file_name().to_string_lossy()));
+        }
+    }
+    dirs.sort();
+    for dir in &dirs {
+        hosts.push((s!("//", dir, env.subdomain_suffix, "/"), s!("//", dir, ".localhost:", port, "/")));
+    }
+    let server = tiny_http::Server::http(addr.as_str())
+        .map_err(|e| Error::invalid(s!("failed to listen on ", addr, ": ", e.to_string())))?;
+    eprintln!("serving {} at http://{}/", args.dest_path.display(), addr);
+    for dir in &dirs {
+        eprintln!("  {} at http://{}.localhost:{}/", dir, dir, port);
+    }
+    for request in server.incoming_requests() {
+        let host = request.headers().iter().find(|header| header.field.equiv("Host")).map(|header| header.value.as_str().to_owned());
+        let (status, ty, body) = handle(args, &hosts, &dirs, host.as_deref(), request.url());
+        let response = tiny_http::Response::from_data(body)
+            .with_status_code(status)
+            .with_header(tiny_http::Header::from_bytes("Content-Type", ty).unwrap());
+        if let Err(e) = request.respond(response) {
+            eprintln!("warning: {}", e);
+        }
+    }
+    Ok(())
+}
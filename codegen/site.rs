@@ -0,0 +1,56 @@
+use serde::Deserialize;
+use crate::{ByteString, Config, Map};
+
+/// `site.yml`
+#[derive(Clone, Debug, Deserialize)]
+pub struct Site {
+    pub environments: Map<Environment>,
+}
+
+/// a deployment target, picked by name with `-c`
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Environment {
+    /// minification and the variants in `static/`
+    pub mode: Config,
+    /// replaces `{{ASSERT}}`
+    pub assets: ByteString,
+    /// replaces `{{REDIRECT}}`, the base of the `/r/`, `/r2/` and `/mirror/` links,
+    /// protocol-relative since the plain page prints it after `https:`
+    pub redirect: ByteString,
+    /// replaces `{{SUBDOMAIN}}`, after the names of subdomain tiles
+    pub subdomain_suffix: ByteString,
+    /// resolves the protocol-relative urls in `tools.json`, `https` if not given
+    #[serde(default = "default_scheme")]
+    pub scheme: ByteString,
+    /// the lines before the commits in the copyright banners
+    pub banner: ByteString,
+    /// dest dir -> the public origin serving it as its root, for `sitemap.xml`
+    #[serde(default)]
+    pub origins: Map<ByteString>,
+}
+
+fn default_scheme() -> ByteString {
+    "https".to_owned()
+}
+
+impl Environment {
+    /// the forms of `assets` and `redirect` the pages are written for
+    pub fn check(&self) -> Result<(), ByteString> {
+        fn origin(url: &str) -> bool {
+            url.strip_prefix("//").is_some_and(|host| !host.is_empty() && !host.contains('/'))
+        }
+        if !origin(&self.redirect) {
+            return Err(s!("redirect `", self.redirect, "` is not a protocol-relative origin like `//r.example.com`"));
+        }
+        if !matches!(self.scheme.as_str(), "http" | "https") {
+            return Err(s!("scheme `", self.scheme, "` is neither `http` nor `https`"));
+        }
+        if self.assets.contains(':') || self.assets.ends_with('/') || (self.assets.starts_with("//") && !origin(&self.assets)) {
+            return Err(s!("assets `", self.assets, "` is neither a protocol-relative origin nor a relative path without a trailing `/`"));
+        }
+        Ok(())
+    }
+}
+
+/// `domains.yml`, domain -> ICP registration number
+pub type Domains = Map<ByteString>;
@@ -0,0 +1,32 @@
+use crate::ByteString;
+
+const XMLNS: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+
+fn escape(s: &str) -> ByteString {
+    let mut res = ByteString::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '\'' => res.push_str("&apos;"),
+            '"' => res.push_str("&quot;"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+/// `sitemap.xml` listing the pages of a host
+pub fn urlset(locs: &[ByteString], lastmod: Option<&str>) -> ByteString {
+    let mut res = s!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"", XMLNS, "\">\n");
+    for loc in locs {
+        res.push_str(&s!("  <url>\n    <loc>", escape(loc), "</loc>\n"));
+        if let Some(lastmod) = lastmod {
+            res.push_str(&s!("    <lastmod>", lastmod, "</lastmod>\n"));
+        }
+        res.push_str("  </url>\n");
+    }
+    res.push_str("</urlset>\n");
+    res
+}
@@ -0,0 +1,528 @@
+use std::path::Path;
+use crate::{ByteString, Map, config::*, error::{Error, Errors}};
+
+const PUBLIC_SIDES: &str = "public/sides.yml";
+const HOME_MAJOR: &str = "home/major.yml";
+const HOME_SIDES: &str = "home/sides.yml";
+const TOOLS_MAJOR: &str = "tool/major.yml";
+const TOOLS_SIDES: &str = "tool/sides.yml";
+const TOOLS_TOOLS: &str = "tool/tools.yml";
+const TOOLS_CATEGORY: &str = "tool/category.yml";
+
+/// the `width` and `height` of each tile size in style.css, in em
+const TILE_SIZES: [(&str, f32, f32); 9] = [
+    ("s", 7.5, 7.5), ("m", 17.0, 7.4), ("l", 17.0, 17.0), ("l1", 17.0, 12.0), ("l2", 17.0, 6.0),
+    ("m1", 16.0, 7.0), ("s1", 7.0, 7.0), ("s2", 5.5, 5.5), ("m2", 12.5, 5.5),
+];
+/// `.tile`, whose padding, border and margin are on both sides
+const TILE_PADDING: f32 = 0.5;
+const TILE_BORDER: f32 = 0.15;
+const TILE_MARGIN: f32 = 0.35;
+const TILE_SPACING: f32 = 2.0 * (TILE_PADDING + TILE_BORDER + TILE_MARGIN);
+/// `.tile-grid-vertical`
+const VERTICAL_WIDTH: f32 = 20.0;
+const VERTICAL_HEIGHT: f32 = 39.0;
+/// `.tile-grid-middle`
+const MIDDLE_WIDTH: f32 = 37.0;
+const MIDDLE_HEIGHT: f32 = 29.5;
+/// `.tile-grid-middle .title`, whose top margin leaves the room for the row of tiles above it,
+/// with the margins in its own font size and the normal line height taken as 1.2
+const TITLE_FONT_SIZE: f32 = 1.8;
+const TITLE_MARGINS: (f32, f32) = (5.9, 0.2);
+const TITLE_LINE_HEIGHT: f32 = 1.2;
+const TITLE_MARGIN_TOP: f32 = TITLE_MARGINS.0 * TITLE_FONT_SIZE;
+const TITLE_HEIGHT: f32 = (TITLE_LINE_HEIGHT + TITLE_MARGINS.1) * TITLE_FONT_SIZE;
+
+/// the heights of the rows the tiles are floated into within `width`, or the size that is unknown
+fn tile_rows(tiles: &[Tile], width: f32) -> Result<Vec<f32>, &str> {
+    let mut rows = Vec::new();
+    let mut row_width = 0.0;
+    let mut row_height: f32 = 0.0;
+    for tile in tiles {
+        let Some(size) = tile.tile.as_deref() else { continue };
+        let Some(&(_, w, h)) = TILE_SIZES.iter().find(|(name, ..)| *name == size) else {
+            return Err(size);
+        };
+        let (w, h) = (w + TILE_SPACING, h + TILE_SPACING);
+        if row_width > 0.0 && row_width + w > width {
+            rows.push(row_height);
+            row_width = 0.0;
+            row_height = 0.0;
+        }
+        row_width += w;
+        row_height = row_height.max(h);
+    }
+    if row_width > 0.0 {
+        rows.push(row_height);
+    }
+    Ok(rows)
+}
+
+pub struct Input<'a> {
+    pub public_sides: &'a [Side],
+    pub home_major: &'a TileColumns,
+    pub home_sides: &'a [Side],
+    pub tools_major: &'a TileGrids,
+    pub tools_sides: &'a [Side],
+    pub tools_tools: &'a [ToolGroup],
+    pub tools_category: &'a Category,
+}
+
+/// names defined in a page, as `name -> (file, logical path)` of the definition
+type Names = Map<(&'static str, ByteString)>;
+
+struct Scope<'b> {
+    page: &'static str,
+    sides: &'b Names,
+    tools: Option<&'b Names>,
+    categories: Option<&'b Names>,
+}
+
+struct Validator<'a> {
+    page_path: &'a Path,
+    errors: Errors,
+    used_tools: Map<()>,
+}
+
+impl<'a> Validator<'a> {
+    fn report(&mut self, file: &str, path: ByteString, msg: ByteString) {
+        self.errors.push(Error::invalid(msg).at(self.page_path.join(file)).path(path));
+    }
+
+    fn define(&mut self, names: &mut Names, kind: &str, name: &ByteString, file: &'static str, path: ByteString) {
+        if let Some((first_file, first_path)) = names.get(name) {
+            let msg = s!("duplicate ", kind, " `", name, "` (first defined in ", first_file, " at ", first_path, ")");
+            self.report(file, path, msg);
+        } else {
+            let _ = names.insert(name.clone(), (file, path));
+        }
+    }
+
+    fn sides(&mut self, names: &mut Names, file: &'static str, sides: &[Side]) {
+        for (i, side) in sides.iter().enumerate() {
+            self.define(names, "side", &side.name, file, s!("[", i.to_string(), "].name"));
+        }
+    }
+
+    fn reference(&mut self, scope: &Scope, file: &str, path: ByteString, action: TileAction, name: &ByteString) {
+        let (kind, names) = match action {
+            TileAction::Side => ("side", Some(scope.sides)),
+            TileAction::Tool => ("tool group", scope.tools),
+            TileAction::Category => ("category", scope.categories),
+            _ => return,
+        };
+        match names {
+            Some(names) if names.contains_key(name) => {
+                if matches!(action, TileAction::Tool) {
+                    let _ = self.used_tools.insert(name.clone(), ());
+                }
+            },
+            Some(_) => self.report(file, path, s!("unknown ", kind, " `", name, "` on ", scope.page, " page")),
+            None => self.report(file, path, s!(kind, " tiles are not supported on ", scope.page, " page")),
+        }
+    }
+
+    fn download(&mut self, path: ByteString, download: &ToolDownload) {
+        let Some(ToolDownloadInfo { sha256, date, .. }) = download.info() else {
+            return;
+        };
+        if let Some(sha256) = sha256 {
+            if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+                self.report(TOOLS_TOOLS, s!(path, ".sha256"), s!("`", sha256, "` is not a sha256 digest"));
+            }
+        }
+        if let Some(date) = date {
+            let is_date = date.len() == 10 && date.bytes().enumerate().all(|(i, b)| if i == 4 || i == 7 { b == b'-' } else { b.is_ascii_digit() });
+            if !is_date {
+                self.report(TOOLS_TOOLS, s!(path, ".date"), s!("`", date, "` is not a `YYYY-MM-DD` date"));
+            }
+        }
+    }
+
+    fn tile(&mut self, scope: &Scope, file: &str, path: ByteString, tile: &Tile, is_category: bool) {
+        if !is_category && tile.tile.is_none() {
+            self.report(file, path.clone(), s!("tile `", tile.name, "` has no `tile`"));
+        }
+        if is_category && tile.title.is_none() {
+            self.report(file, path.clone(), s!("category item `", tile.name, "` has no `title`"));
+        }
+        if matches!(tile.action, TileAction::Subdomain) && tile.subdomain.is_none() {
+            self.report(file, path.clone(), s!("subdomain tile `", tile.name, "` has no `subdomain`"));
+        }
+        self.reference(scope, file, path, tile.action, &tile.name);
+    }
+
+    fn tiles(&mut self, scope: &Scope, file: &str, path: &str, tiles: &[Tile]) {
+        for (i, tile) in tiles.iter().enumerate() {
+            self.tile(scope, file, s!(path, "[", i.to_string(), "]"), tile, false);
+        }
+    }
+
+    fn side_tiles(&mut self, scope: &Scope, file: &str, sides: &[Side]) {
+        for (i, side) in sides.iter().enumerate() {
+            let path = s!("[", i.to_string(), "]");
+            if let Some(tiles) = &side.tiles {
+                self.tiles(scope, file, &s!(path, ".tiles"), tiles);
+            }
+            if let Some(TileTemplate { template, tiles }) = &side.templated {
+                let names: Vec<&ByteString> = match tiles {
+                    TileTemplateTiles::WithoutTitle(tiles) => tiles.iter().collect(),
+                    TileTemplateTiles::WithTitle(tiles) => tiles.keys().collect(),
+                };
+                for name in names {
+                    self.reference(scope, file, s!(path, ".templated.tiles.", name), template.action, name);
+                }
+            }
+        }
+    }
+}
+
+fn is_hidden(tool: &Tool) -> bool {
+    tool.hidden.unwrap_or(false)
+}
+
+fn group_name(group: &ToolGroup) -> Option<&ByteString> {
+    let single = (group.list.len() == 1) && group.name.is_none();
+    group.name.as_ref().or_else(|| single.then(|| &group.list[0].name))
+}
+
+/// resolves every cross reference in the page data, so that the codegen itself can rely on them
+pub fn validate(page_path: &Path, input: Input) -> Errors {
+    let Input { public_sides, home_major, home_sides, tools_major, tools_sides, tools_tools, tools_category } = input;
+    let mut v = Validator { page_path, errors: Errors::new(), used_tools: Map::new() };
+
+    // definitions
+
+    let mut public_side_names = Names::new();
+    v.sides(&mut public_side_names, PUBLIC_SIDES, public_sides);
+    let mut home_side_names = public_side_names.clone();
+    v.sides(&mut home_side_names, HOME_SIDES, home_sides);
+    let mut tools_side_names = public_side_names;
+    v.sides(&mut tools_side_names, TOOLS_SIDES, tools_sides);
+
+    let tab_path = |t: usize, i: usize, j: usize| s!("tabs[", t.to_string(), "].content[", i.to_string(), "].content[", j.to_string(), "]");
+    let mut categories = Names::new();
+    for (t, tab) in tools_category.tabs.iter().enumerate() {
+        for (i, group) in tab.content.iter().enumerate() {
+            for (j, item) in group.content.iter().enumerate() {
+                if matches!(item.action, TileAction::Category) {
+                    let path = tab_path(t, i, j);
+                    v.define(&mut categories, "category", &item.name, TOOLS_CATEGORY, path);
+                }
+            }
+        }
+    }
+
+    let mut tools = Names::new();
+    let mut hidden_tools = Map::new();
+    let mut groups = Names::new();
+    let mut cross_notice_groups = Names::new();
+    for (i, group) in tools_tools.iter().enumerate() {
+        let path = s!("[", i.to_string(), "]");
+        match group_name(group) {
+            Some(name) if name == "non-index" => {},
+            Some(name) => {
+                v.define(&mut groups, "tool group", name, TOOLS_TOOLS, path.clone());
+                if group.title.is_none() && group.name.is_some() {
+                    v.report(TOOLS_TOOLS, path.clone(), s!("tool group `", name, "` must have a `title`"));
+                }
+                if group.list.iter().all(is_hidden) {
+                    v.report(TOOLS_TOOLS, path.clone(), s!("every tool of tool group `", name, "` is hidden, so the group would be empty"));
+                }
+            },
+            None => v.report(TOOLS_TOOLS, path.clone(), s!("tool group without exactly one tool must have a `name`")),
+        }
+        if group.cross_notice.is_some() {
+            match &group.name {
+                Some(name) => v.define(&mut cross_notice_groups, "cross notice group", name, TOOLS_TOOLS, path.clone()),
+                None => v.report(TOOLS_TOOLS, path.clone(), s!("tool group with `cross_notice` must have a `name`")),
+            }
+        }
+        for (j, tool) in group.list.iter().enumerate() {
+            v.define(&mut tools, "tool", &tool.name, TOOLS_TOOLS, s!(path, ".list[", j.to_string(), "]"));
+            if is_hidden(tool) {
+                let _ = hidden_tools.insert(tool.name.clone(), ());
+            }
+        }
+    }
+
+    // the references by alias are already resolved by `codegen::resolve_aliases`
+    let mut aliases = Names::new();
+    for (i, group) in tools_tools.iter().enumerate() {
+        for (j, tool_item) in group.list.iter().enumerate() {
+            for (k, alias) in tool_item.aliases.iter().flatten().enumerate() {
+                let path = s!("[", i.to_string(), "].list[", j.to_string(), "].aliases[", k.to_string(), "]");
+                if !crate::redirects::is_key_name(alias) {
+                    v.report(TOOLS_TOOLS, path, s!("alias `", alias, "` can't be used in redirect keys, use letters, digits, `.`, `_`, `~` and `-`"));
+                } else if let Some((_, first_path)) = tools.get(alias) {
+                    v.report(TOOLS_TOOLS, path, s!("alias `", alias, "` is already the name of the tool at ", first_path));
+                } else if groups.contains_key(alias) {
+                    v.report(TOOLS_TOOLS, path, s!("alias `", alias, "` is already the name of a tool group"));
+                } else {
+                    v.define(&mut aliases, "alias", alias, TOOLS_TOOLS, path);
+                }
+            }
+        }
+    }
+
+    // references
+
+    let home = Scope { page: "home", sides: &home_side_names, tools: None, categories: None };
+    let tool = Scope { page: "tool", sides: &tools_side_names, tools: Some(&groups), categories: Some(&categories) };
+
+    v.side_tiles(&home, PUBLIC_SIDES, public_sides);
+    v.side_tiles(&tool, PUBLIC_SIDES, public_sides);
+    v.side_tiles(&home, HOME_SIDES, home_sides);
+    v.side_tiles(&tool, TOOLS_SIDES, tools_sides);
+
+    for (i, column) in home_major.iter().enumerate() {
+        v.tiles(&home, HOME_MAJOR, &s!("[", i.to_string(), "]"), column);
+    }
+
+    let mut last_placement = TileGridPlacement::Vertical;
+    let mut titles = 0;
+    for (i, section) in tools_major.sections.iter().enumerate() {
+        let path = s!("sections[", i.to_string(), "]");
+        let placement = section.placement;
+        if placement < last_placement {
+            v.report(TOOLS_MAJOR, path.clone(), s!(
+                "section is placed `", placement.as_str(), "` after a `", last_placement.as_str(), "` one, ",
+                "but sections are laid out as the vertical strips, then the middle block, then the trailing tiles"
+            ));
+        } else if placement == TileGridPlacement::Trailing && last_placement == TileGridPlacement::Trailing {
+            v.report(TOOLS_MAJOR, path.clone(), s!("only one section can be placed `trailing`, as its tiles directly follow the middle block"));
+        }
+        last_placement = last_placement.max(placement);
+        match (placement, &section.title) {
+            (TileGridPlacement::Vertical, Some(_)) => v.report(TOOLS_MAJOR, path.clone(), s!("sections placed `vertical` have no room for a `title`")),
+            (TileGridPlacement::Middle | TileGridPlacement::Trailing, None) => {
+                v.report(TOOLS_MAJOR, path.clone(), s!("sections placed `", placement.as_str(), "` must have a `title`"));
+            },
+            _ => {},
+        }
+        if placement != TileGridPlacement::Vertical {
+            titles += 1;
+        }
+        match placement {
+            TileGridPlacement::Vertical => match tile_rows(&section.content, VERTICAL_WIDTH) {
+                Ok(rows) if rows.iter().sum::<f32>() > VERTICAL_HEIGHT => v.report(TOOLS_MAJOR, path.clone(), s!(
+                    "section takes ", rows.iter().sum::<f32>().to_string(), "em of height, but a vertical strip is ",
+                    VERTICAL_HEIGHT.to_string(), "em high"
+                )),
+                Ok(_) => {},
+                Err(size) => v.report(TOOLS_MAJOR, path.clone(), s!("unknown tile size `", size, "`")),
+            },
+            // the next title clears a single row of tiles with its top margin
+            TileGridPlacement::Middle => match tile_rows(&section.content, MIDDLE_WIDTH) {
+                Ok(rows) if rows.len() > 1 => v.report(TOOLS_MAJOR, path.clone(), s!(
+                    "section takes ", rows.len().to_string(), " rows, but a middle section has room for one row of ",
+                    MIDDLE_WIDTH.to_string(), "em"
+                )),
+                Ok(rows) if rows.iter().any(|&h| h > TITLE_MARGIN_TOP) => v.report(TOOLS_MAJOR, path.clone(), s!(
+                    "section has tiles higher than the ", TITLE_MARGIN_TOP.to_string(), "em left above the next title"
+                )),
+                Ok(_) => {},
+                Err(size) => v.report(TOOLS_MAJOR, path.clone(), s!("unknown tile size `", size, "`")),
+            },
+            // the trailing tiles flow around the middle block, so only the data knows how many fit
+            TileGridPlacement::Trailing => match section.capacity {
+                Some(capacity) if section.content.len() > capacity => v.report(TOOLS_MAJOR, path.clone(), s!(
+                    "section has ", section.content.len().to_string(), " tiles, but its `capacity` is ", capacity.to_string(),
+                    ": the trailing space of the page has no room for more"
+                )),
+                Some(_) => {},
+                None => v.report(TOOLS_MAJOR, path.clone(), s!("sections placed `trailing` must have a `capacity`")),
+            },
+        }
+        if placement != TileGridPlacement::Trailing && section.capacity.is_some() {
+            v.report(TOOLS_MAJOR, s!(path, ".capacity"), s!(
+                "only sections placed `trailing` have a `capacity`, the others are checked against the tile sizes"
+            ));
+        }
+        v.tiles(&tool, TOOLS_MAJOR, &s!(path, ".content"), &section.content);
+    }
+
+    let titles_height = |titles: usize| titles as f32 * TITLE_HEIGHT + titles.saturating_sub(1) as f32 * TITLE_MARGIN_TOP;
+    if titles_height(titles) > MIDDLE_HEIGHT {
+        let max = (1..).take_while(|&n| titles_height(n) <= MIDDLE_HEIGHT).last().unwrap_or(0);
+        v.report(TOOLS_MAJOR, s!("sections"), s!(
+            "there are ", titles.to_string(), " titled sections, but the middle block has room for the titles of ", max.to_string()
+        ));
+    }
+
+    for (t, tab) in tools_category.tabs.iter().enumerate() {
+        for (i, group) in tab.content.iter().enumerate() {
+            for (j, item) in group.content.iter().enumerate() {
+                v.tile(&tool, TOOLS_CATEGORY, tab_path(t, i, j), item, true);
+            }
+        }
+    }
+
+    let mut used_categories = Map::new();
+    let mut crossed = Vec::new();
+    let mut replacements = Vec::new();
+    for (i, group) in tools_tools.iter().enumerate() {
+        for (j, tool_item) in group.list.iter().enumerate() {
+            let path = s!("[", i.to_string(), "].list[", j.to_string(), "]");
+            for (field, names) in [("category", &tool_item.category), ("cross", &tool_item.cross), ("cross_top", &tool_item.cross_top)] {
+                let (kind, defined) = if field == "category" { ("category", &categories) } else { ("tool group", &groups) };
+                for (k, name) in names.iter().flatten().enumerate() {
+                    let path = s!(path, ".", field, "[", k.to_string(), "]");
+                    if !defined.contains_key(name) {
+                        v.report(TOOLS_TOOLS, path, s!("unknown ", kind, " `", name, "`"));
+                    } else if field == "category" {
+                        // hidden tools are left out of the categories
+                        if !is_hidden(tool_item) {
+                            let _ = used_categories.insert(name.clone(), ());
+                        }
+                    } else {
+                        crossed.push((name.clone(), &tool_item.name));
+                    }
+                }
+            }
+            if let Some(replaced_by) = &tool_item.replaced_by {
+                replacements.push((s!(path, ".replaced_by"), tool_item, replaced_by));
+            }
+            if let Some(cross_notice) = &tool_item.cross_notice {
+                for notice_group in cross_notice.keys() {
+                    if !cross_notice_groups.contains_key(notice_group) {
+                        let path = s!(path, ".cross_notice.", notice_group);
+                        v.report(TOOLS_TOOLS, path, s!("unknown cross notice group `", notice_group, "`"));
+                    }
+                }
+            }
+            let links = &tool_item.links;
+            for (link, download) in links.downloads.iter().flatten() {
+                v.download(s!(path, ".downloads.", link), download);
+            }
+            for (group, downloads) in links.downloads_groups.iter().flatten() {
+                for (link, download) in downloads {
+                    v.download(s!(path, ".downloads_groups.", group, ".", link), download);
+                }
+            }
+            if let Some(websites_tile) = &links.websites_tile {
+                match &links.websites_tile_template {
+                    Some(template) => {
+                        for link in websites_tile.keys() {
+                            let tile_name = s!(tool_item.name, "-", link);
+                            v.reference(&tool, TOOLS_TOOLS, s!(path, ".websites_tile.", link), template.action, &tile_name);
+                        }
+                    },
+                    None => v.report(TOOLS_TOOLS, path, s!("`websites_tile` requires `websites_tile_template`")),
+                }
+            }
+        }
+    }
+
+    // the replacement is linked to by its tool group and its anchor on the plain page
+    let mut listed_tools = Map::new();
+    for group in tools_tools {
+        if group_name(group).is_some_and(|name| name != "non-index") {
+            for tool_item in &group.list {
+                let _ = listed_tools.insert(tool_item.name.clone(), ());
+            }
+        }
+    }
+    for (_, tool_name) in &crossed {
+        let _ = listed_tools.insert((*tool_name).clone(), ());
+    }
+    for (path, tool_item, replaced_by) in replacements {
+        if tool_item.deprecated.is_none() {
+            v.report(TOOLS_TOOLS, path, s!("`replaced_by` requires `deprecated`"));
+        } else if replaced_by == &tool_item.name {
+            v.report(TOOLS_TOOLS, path, s!("tool `", replaced_by, "` can't replace itself"));
+        } else if !tools.contains_key(replaced_by) {
+            v.report(TOOLS_TOOLS, path, s!("unknown tool `", replaced_by, "`"));
+        } else if hidden_tools.contains_key(replaced_by) {
+            v.report(TOOLS_TOOLS, path, s!("tool `", replaced_by, "` is hidden, so it can't be linked to as the replacement"));
+        } else if !listed_tools.contains_key(replaced_by) {
+            v.report(TOOLS_TOOLS, path, s!("tool `", replaced_by, "` is in no tool group, so it can't be linked to as the replacement"));
+        }
+    }
+
+    // reachability
+
+    for (name, (file, path)) in &categories {
+        if !used_categories.contains_key(name) {
+            let (file, path) = (*file, path.clone());
+            v.report(file, path, s!("category `", name, "` is unreachable: no visible tool belongs to it"));
+        }
+    }
+
+    let mut reachable_tools = Map::new();
+    for group in tools_tools {
+        if group_name(group).is_some_and(|name| v.used_tools.contains_key(name)) {
+            for tool_item in &group.list {
+                let _ = reachable_tools.insert(tool_item.name.clone(), ());
+            }
+        }
+        for tool_item in &group.list {
+            if tool_item.category.as_ref().is_some_and(|category| !category.is_empty()) {
+                let _ = reachable_tools.insert(tool_item.name.clone(), ());
+            }
+        }
+    }
+    for (group_name, tool_name) in crossed {
+        if v.used_tools.contains_key(&group_name) {
+            let _ = reachable_tools.insert(tool_name.clone(), ());
+        }
+    }
+    for (name, (file, path)) in &tools {
+        if !reachable_tools.contains_key(name) && !hidden_tools.contains_key(name) {
+            let (file, path) = (*file, path.clone());
+            v.report(file, path, s!("tool `", name, "` is unreachable: not in any category or tool group shown by a tile"));
+        }
+    }
+
+    v.errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STYLE: &str = include_str!("../dynamic/code/style.css");
+
+    /// the `index`th value of `property` in the top-level rule for `selector`, in em
+    fn em(selector: &str, property: &str, index: usize) -> f32 {
+        let start = STYLE.find(&format!("\n{} {{\n", selector)).unwrap_or_else(|| panic!("no rule for `{}`", selector));
+        let rule = &STYLE[start..];
+        let body = &rule[(rule.find('{').unwrap() + 1)..rule.find('}').unwrap()];
+        let value = body.split(';')
+            .filter_map(|declaration| declaration.split_once(':'))
+            .find(|(name, _)| name.trim() == property)
+            .unwrap_or_else(|| panic!("no `{}` in `{}`", property, selector)).1;
+        let value = value.split_whitespace().nth(index).unwrap();
+        value.trim_end_matches("em").parse().unwrap_or_else(|_| panic!("`{}` of `{}` is not in em", property, selector))
+    }
+
+    #[test]
+    fn tile_sizes_match_style() {
+        for (name, width, height) in TILE_SIZES {
+            let selector = format!(".tile.{}", name);
+            assert_eq!((em(&selector, "width", 0), em(&selector, "height", 0)), (width, height), "{}", selector);
+        }
+        // every size in the stylesheet is known
+        for line in STYLE.lines() {
+            if let Some(name) = line.strip_prefix(".tile.").and_then(|rest| rest.strip_suffix(" {")) {
+                if !name.contains([' ', '.']) {
+                    assert!(TILE_SIZES.iter().any(|(size, ..)| *size == name), "`.tile.{}` is missing", name);
+                }
+            }
+        }
+        assert_eq!(em(".tile", "padding", 0), TILE_PADDING);
+        assert_eq!(em(".tile", "border", 0), TILE_BORDER);
+        assert_eq!(em(".tile", "margin", 0), TILE_MARGIN);
+    }
+
+    #[test]
+    fn grid_sizes_match_style() {
+        assert_eq!(em(".tile-grid-vertical", "width", 0), VERTICAL_WIDTH);
+        assert_eq!(em(".tile-grid-vertical", "height", 0), VERTICAL_HEIGHT);
+        assert_eq!(em(".tile-grid-middle", "width", 0), MIDDLE_WIDTH);
+        assert_eq!(em(".tile-grid-middle", "height", 0), MIDDLE_HEIGHT);
+        assert_eq!(em(".tile-grid-middle .title", "font-size", 0), TITLE_FONT_SIZE);
+        assert_eq!(em(".tile-grid-middle .title", "margin", 0), TITLE_MARGINS.0);
+        assert_eq!(em(".tile-grid-middle .title", "margin", 2), TITLE_MARGINS.1);
+    }
+}
@@ -0,0 +1,117 @@
+use std::{fs, path::{Path, PathBuf}, thread, time::{Duration, SystemTime}};
+use crate::{Args, Builder, CONTENT_PATHS, DOMAINS, REDIRECTS, SITE, env, error::Errors, load_env};
+
+/// build, then rebuild only the affected outputs whenever a source changes
+#[derive(argh::FromArgs)]
+#[argh(subcommand, name = "watch")]
+pub struct WatchArgs {
+    /// polling interval in milliseconds (default 300)
+    #[argh(option, default = "300")]
+    interval: u64,
+}
+
+type Snapshot = indexmap::IndexMap<PathBuf, SystemTime>;
+
+fn scan(path: &Path, snapshot: &mut Snapshot) {
+    let Ok(entries) = fs::read_dir(path) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else { continue };
+        if metadata.is_dir() {
+            scan(&path, snapshot);
+        } else if let Ok(modified) = metadata.modified() {
+            let _ = snapshot.insert(path, modified);
+        }
+    }
+}
+
+fn snapshot(base_path: &Path) -> Snapshot {
+    let mut snapshot = Snapshot::new();
+    for path in CONTENT_PATHS {
+        let path = base_path.join(path);
+        match fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() => if let Ok(modified) = metadata.modified() {
+                let _ = snapshot.insert(path, modified);
+            },
+            _ => scan(&path, &mut snapshot),
+        }
+    }
+    snapshot
+}
+
+/// added, modified and removed files
+fn changes(old: &Snapshot, new: &Snapshot) -> Vec<PathBuf> {
+    let mut changed = Vec::new();
+    for (path, modified) in new {
+        if old.get(path) != Some(modified) {
+            changed.push(path.clone());
+        }
+    }
+    for path in old.keys() {
+        if !new.contains_key(path) {
+            changed.push(path.clone());
+        }
+    }
+    changed
+}
+
+fn report(errors: Errors) {
+    if !errors.is_empty() {
+        eprintln!("{}", errors);
+    }
+}
+
+pub fn watch(args: &Args, WatchArgs { interval }: &WatchArgs) -> Result<(), Errors> {
+    let interval = Duration::from_millis(*interval);
+    let mut files = snapshot(&args.base_path);
+    let mut builder = None;
+
+    loop {
+        // a full build is needed at the start, and again after the sources were too broken to load
+        if builder.is_none() {
+            match Builder::new(args, &args.dest_path, true) {
+                Ok(mut new_builder) => {
+                    let mut errors = Errors::new();
+                    new_builder.build_all(&mut errors);
+                    eprintln!("built {} pages", new_builder.page_sources.len());
+                    report(errors);
+                    builder = Some(new_builder);
+                },
+                Err(errors) => report(errors),
+            }
+        }
+
+        let changed = loop {
+            thread::sleep(interval);
+            let new_files = snapshot(&args.base_path);
+            let changed = changes(&files, &new_files);
+            files = new_files;
+            if !changed.is_empty() {
+                break changed;
+            }
+        };
+
+        if changed.contains(&args.base_path.join(SITE)) {
+            // the environment is loaded once at the start
+            match load_env(args) {
+                Ok(new_env) if &new_env != env() => eprintln!("warning: {} changed, restart to apply the environment", SITE),
+                Ok(_) => {},
+                Err(error) => report(error.into()),
+            }
+        }
+        // these are only loaded by a full build
+        if [DOMAINS, REDIRECTS].iter().any(|name| changed.contains(&args.base_path.join(name))) {
+            builder = None;
+            continue;
+        }
+
+        if let Some(builder) = &mut builder {
+            let mut errors = Errors::new();
+            let rebuilt = builder.rebuild(&changed, &mut errors);
+            if !rebuilt.is_empty() {
+                eprintln!("rebuilt {}", rebuilt.join(", "));
+            }
+            report(errors);
+        }
+    }
+}
@@ -13,14 +13,29 @@ type ToolCategoryType = Record<string, {
     list: string[];
 }>;
 
-type ToolAllType = Record<string, string>;
+type ToolSearchKeyType = {
+    text: string;
+    // 仅标题和关键词带拼音，且与 text 相同时省略
+    pinyin?: string;
+    initials?: string;
+};
+
+type ToolSearchType = {
+    name: string;
+    group: string | null;
+    categories: string[];
+    fields: {
+        weight: number;
+        tokens: ToolSearchKeyType[];
+    }[];
+}[];
 
 type ToolCrossType = Record<string, Record<string, string>>;
 
 type ToolData = {
     index: ToolIndexType;
     category: ToolCategoryType;
-    all: ToolAllType;
+    search: ToolSearchType;
     cross: ToolCrossType;
 };
 
@@ -146,7 +161,7 @@ if (DATA.page_type === "tool") {
     back!.onclick = (e: MouseEvent) => {
         // 用来阻止冒泡
         e.stopPropagation();
-        globalThis.open("//ldt.pc.wiki/", "_blank");
+        globalThis.open("//ldt{{SUBDOMAIN}}/", "_blank");
     };
 
     changeMajor!.onclick = (e: MouseEvent) => {
@@ -344,17 +359,43 @@ const renderSide = (id: string) => {
     }
 };
 
+// 前缀匹配 > 首字母匹配 > 子串匹配
+// 首字母只按前缀匹配，否则很短的关键词几乎能匹配所有条目
+const searchRank = (key: ToolSearchKeyType, keyword: string) => {
+    if (key.text.startsWith(keyword) || key.pinyin?.startsWith(keyword)) {
+        return 3;
+    }
+    if (key.initials?.startsWith(keyword)) {
+        return 2;
+    }
+    if (key.text.includes(keyword) || key.pinyin?.includes(keyword)) {
+        return 1;
+    }
+    return 0;
+};
+
 const renderSearch = (keywordText: string) => {
     if (DATA.page_type === "tool") {
-        const all = DATA.tool.all;
+        const keyword = keywordText.toLowerCase().replace(/\s/g, "");
         const content = document.getElementById("search-content")!;
         clear(content);
-        for (const tool of Object.keys(all)) {
-            if (tool.toLowerCase().includes(keywordText.toLowerCase())) {
-                content.appendChild(cloneTemplate(`tool-${all[tool]}`));
-                // showDetail(side.getElementsByClassName("item")[0] as HTMLElement);
-                // return;
+        const results = DATA.tool.search.map(({ name, fields }) => {
+            // 权重高的字段优先，同一字段内按匹配方式排序
+            let rank = 0;
+            for (const { weight, tokens } of fields) {
+                for (const token of tokens) {
+                    const tokenRank = searchRank(token, keyword);
+                    if (tokenRank > 0) {
+                        rank = Math.max(rank, weight * 4 + tokenRank);
+                    }
+                }
             }
+            return { name, rank };
+        }).filter(({ rank }) => rank > 0);
+        // sort 是稳定的，同级结果保持原顺序
+        results.sort((a, b) => b.rank - a.rank);
+        for (const { name } of results) {
+            content.appendChild(cloneTemplate(`tool-${name}`));
         }
     }
 };
@@ -465,43 +506,42 @@ const showDetail = (e: HTMLElement) => {
 };
 
 const initCategory = () => {
-    const tool_button = document.getElementById("tool-button")!;
-    const link_button = document.getElementById("link-button")!;
-    const tool_list = document.getElementById("tool-list")!;
-    const link_list = document.getElementById("link-list")!;
-
-    tool_button.className = "selected";
-    link_list.style.opacity = "0";
-    link_list.style.pointerEvents = "none";
-    tool_list.onclick = link_list.onclick = (e) => {
-        if (
-            e.composedPath()[0] === tool_list ||
-            e.composedPath()[0] === link_list
-        ) {
-            sideClose();
+    const buttons: HTMLElement[] = [];
+    const lists: HTMLElement[] = [];
+    for (let i = 0; ; i++) {
+        const button = document.getElementById(`category-button-${i}`);
+        const list = document.getElementById(`category-list-${i}`);
+        if (button === null || list === null) {
+            break;
         }
-    };
+        buttons.push(button);
+        lists.push(list);
+    }
 
-    tool_button.onclick = (e: MouseEvent) => {
-        e.stopPropagation();
-        tool_list.style.opacity = "1";
-        tool_list.style.pointerEvents = "all";
-        link_list.style.opacity = "0";
-        link_list.style.pointerEvents = "none";
-        link_button.classList.remove();
-        tool_button.className = "selected";
-        link_button.className = "";
+    const select = (index: number) => {
+        buttons.forEach((button, i) => {
+            button.classList.toggle("selected", i === index);
+        });
+        lists.forEach((list, i) => {
+            list.style.opacity = i === index ? "1" : "0";
+            list.style.pointerEvents = i === index ? "all" : "none";
+        });
     };
 
-    link_button.onclick = (e: MouseEvent) => {
-        e.stopPropagation();
-        tool_list.style.opacity = "0";
-        tool_list.style.pointerEvents = "none";
-        link_list.style.opacity = "1";
-        link_list.style.pointerEvents = "all";
-        tool_button.className = "";
-        link_button.className = "selected";
-    };
+    select(0);
+    lists.forEach((list) => {
+        list.onclick = (e) => {
+            if (e.composedPath()[0] === list) {
+                sideClose();
+            }
+        };
+    });
+    buttons.forEach((button, i) => {
+        button.onclick = (e: MouseEvent) => {
+            e.stopPropagation();
+            select(i);
+        };
+    });
 };
 
 const renderToolMajor = (id: MajorId) => {
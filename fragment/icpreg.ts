@@ -7,10 +7,9 @@ const check_domain = (current_domain: string, map: Record<string, string>) => {
     return null;
 }
 
-const map = {
-    "pc.wiki": "鲁ICP备2023022036号",
-    "ldtstore.com.cn": "鲁ICP备2021014114号",
-}
+// replaced with domains.yml by the codegen
+declare const __ICPREG_MAP__: Record<string, string>;
+const map = __ICPREG_MAP__;
 
 const current_icpreg = check_domain(location.hostname, map);
 const mount = document.getElementById("icpreg-mount");